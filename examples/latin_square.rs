@@ -0,0 +1,64 @@
+// fills an N by N grid so every row and column holds every number once,
+// run with `cargo run --example latin_square -- 4`
+use std::env;
+
+use sudoku::{ExactCoverProblem, SolvingState};
+
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+enum Item {
+    Cell { row: usize, column: usize },
+    RowNumber { row: usize, number: usize },
+    ColumnNumber { column: usize, number: usize },
+}
+
+fn main() {
+    let n: usize = env::args()
+        .nth(1)
+        .map(|arg| arg.parse().expect("the square size is a number"))
+        .unwrap_or(4);
+
+    let mut problem = ExactCoverProblem::new();
+    for a in 0..n {
+        for b in 0..n {
+            problem.add_item(Item::Cell { row: a, column: b });
+            problem.add_item(Item::RowNumber { row: a, number: b });
+            problem.add_item(Item::ColumnNumber {
+                column: a,
+                number: b,
+            });
+        }
+    }
+    for row in 0..n {
+        for column in 0..n {
+            for number in 0..n {
+                problem.add_option(
+                    (row, column, number),
+                    [
+                        Item::Cell { row, column },
+                        Item::RowNumber { row, number },
+                        Item::ColumnNumber { column, number },
+                    ],
+                );
+            }
+        }
+    }
+
+    let Some(values) = problem.first_solution() else {
+        return;
+    };
+    let mut square = vec![vec![0; n]; n];
+    for &(row, column, number) in values {
+        square[row][column] = number + 1;
+    }
+    for line in square {
+        let line: Vec<String> = line.iter().map(ToString::to_string).collect();
+        println!("{}", line.join(" "));
+    }
+
+    let mut count: u64 = 0;
+    problem.solve(|_| {
+        count += 1;
+        SolvingState::Continue
+    });
+    println!("{count} latin squares of size {n}");
+}
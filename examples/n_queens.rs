@@ -0,0 +1,91 @@
+// places N queens on an N by N board so that no two attack each other,
+// run with `cargo run --example n_queens -- 8`
+use std::env;
+
+use sudoku::{ExactCoverProblem, SolvingState};
+
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+enum Item {
+    Row(usize),
+    Column(usize),
+    // cells with the same row + column
+    Diagonal(usize),
+    // cells with the same row - column
+    AntiDiagonal(isize),
+}
+
+// a queen in every row and every column, and at most one on every diagonal
+fn problem(n: usize) -> ExactCoverProblem<Item, (usize, usize)> {
+    let mut problem = ExactCoverProblem::new();
+    for index in 0..n {
+        problem.add_item(Item::Row(index));
+        problem.add_item(Item::Column(index));
+    }
+    // a diagonal can stay empty, so it only has to be covered at most once
+    for index in 0..2 * n {
+        problem.add_secondary_item(Item::Diagonal(index));
+        problem.add_secondary_item(Item::AntiDiagonal(index as isize - n as isize));
+    }
+    for row in 0..n {
+        for column in 0..n {
+            problem.add_option(
+                (row, column),
+                [
+                    Item::Row(row),
+                    Item::Column(column),
+                    Item::Diagonal(row + column),
+                    Item::AntiDiagonal(row as isize - column as isize),
+                ],
+            );
+        }
+    }
+    problem
+}
+
+fn count_solutions(problem: &ExactCoverProblem<Item, (usize, usize)>) -> usize {
+    let mut count = 0;
+    problem.solve(|_| {
+        count += 1;
+        SolvingState::Continue
+    });
+    count
+}
+
+fn main() {
+    let n: usize = env::args()
+        .nth(1)
+        .map(|arg| arg.parse().expect("the board size is a number"))
+        .unwrap_or(8);
+
+    let problem = problem(n);
+    let Some(queens) = problem.first_solution() else {
+        println!("no way to place {n} queens");
+        return;
+    };
+    for row in 0..n {
+        let line: String = (0..n)
+            .map(|column| {
+                if queens.contains(&&(row, column)) {
+                    'Q'
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        println!("{line}");
+    }
+
+    println!("{} solutions", count_solutions(&problem));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_the_known_solutions() {
+        for (n, solutions) in [(1, 1), (2, 0), (3, 0), (4, 2), (6, 4), (8, 92)] {
+            assert_eq!(count_solutions(&problem(n)), solutions, "{n} queens");
+        }
+    }
+}
@@ -0,0 +1,104 @@
+// tiles a 6 by 10 rectangle with the twelve pentominoes,
+// run with `cargo run --example pentominoes`
+use std::collections::BTreeSet;
+
+use sudoku::ExactCoverProblem;
+
+const ROWS: i32 = 6;
+const COLUMNS: i32 = 10;
+
+// every piece as the (row, column) of its five squares
+const PIECES: [(char, [(i32, i32); 5]); 12] = [
+    ('F', [(0, 1), (0, 2), (1, 0), (1, 1), (2, 1)]),
+    ('I', [(0, 0), (1, 0), (2, 0), (3, 0), (4, 0)]),
+    ('L', [(0, 0), (1, 0), (2, 0), (3, 0), (3, 1)]),
+    ('N', [(0, 1), (1, 1), (2, 0), (2, 1), (3, 0)]),
+    ('P', [(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)]),
+    ('T', [(0, 0), (0, 1), (0, 2), (1, 1), (2, 1)]),
+    ('U', [(0, 0), (0, 2), (1, 0), (1, 1), (1, 2)]),
+    ('V', [(0, 0), (1, 0), (2, 0), (2, 1), (2, 2)]),
+    ('W', [(0, 0), (1, 0), (1, 1), (2, 1), (2, 2)]),
+    ('X', [(0, 1), (1, 0), (1, 1), (1, 2), (2, 1)]),
+    ('Y', [(0, 1), (1, 0), (1, 1), (2, 1), (3, 1)]),
+    ('Z', [(0, 0), (0, 1), (1, 1), (2, 1), (2, 2)]),
+];
+
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+enum Item {
+    Piece(char),
+    Square(i32, i32),
+}
+
+// every rotation and reflection of a piece, moved so its top left corner is at (0, 0)
+fn orientations(squares: &[(i32, i32)]) -> BTreeSet<Vec<(i32, i32)>> {
+    let mut orientations = BTreeSet::new();
+    let mut current = squares.to_vec();
+    for _ in 0..2 {
+        for _ in 0..4 {
+            current = current
+                .iter()
+                .map(|&(row, column)| (column, -row))
+                .collect();
+            let top = current.iter().map(|square| square.0).min().unwrap();
+            let left = current.iter().map(|square| square.1).min().unwrap();
+            let mut normalized: Vec<(i32, i32)> = current
+                .iter()
+                .map(|&(row, column)| (row - top, column - left))
+                .collect();
+            normalized.sort();
+            orientations.insert(normalized);
+        }
+        current = current
+            .iter()
+            .map(|&(row, column)| (row, -column))
+            .collect();
+    }
+    orientations
+}
+
+fn main() {
+    let mut problem = ExactCoverProblem::new();
+    for (name, _) in PIECES {
+        problem.add_item(Item::Piece(name));
+    }
+    for row in 0..ROWS {
+        for column in 0..COLUMNS {
+            problem.add_item(Item::Square(row, column));
+        }
+    }
+
+    for (name, squares) in PIECES {
+        for orientation in orientations(&squares) {
+            for (top, left) in (0..ROWS).flat_map(|row| (0..COLUMNS).map(move |c| (row, c))) {
+                let placed: Vec<(i32, i32)> = orientation
+                    .iter()
+                    .map(|&(row, column)| (top + row, left + column))
+                    .collect();
+                if placed
+                    .iter()
+                    .all(|&(row, column)| row < ROWS && column < COLUMNS)
+                {
+                    let items = placed
+                        .iter()
+                        .map(|&(row, column)| Item::Square(row, column))
+                        .chain([Item::Piece(name)]);
+                    problem.add_option((name, placed.clone()), items);
+                }
+            }
+        }
+    }
+
+    let Some(pieces) = problem.first_solution() else {
+        println!("the pieces do not fit");
+        return;
+    };
+    let mut grid = vec![vec!['.'; COLUMNS as usize]; ROWS as usize];
+    for (name, squares) in pieces {
+        for &(row, column) in squares {
+            grid[row as usize][column as usize] = *name;
+        }
+    }
+    for line in grid {
+        println!("{}", line.into_iter().collect::<String>());
+    }
+}
@@ -0,0 +1,364 @@
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+use rand::{thread_rng, Rng};
+use std::{
+    env,
+    error::Error,
+    fs,
+    io::{self, Read},
+    path::Path,
+    process::ExitCode,
+    str::FromStr,
+    time::Duration,
+};
+use sudoku::{
+    logic, Choice, Format, Generator, Grade, ParsedPuzzle, Shape, Solution, SolvingState, Sudoku,
+    Symmetry, Variant,
+};
+
+const USAGE: &str = "\
+usage: sudoku <command> [options]
+
+commands:
+    solve <file> [--to F]               print the solution of every puzzle in <file>
+    generate [--count N]                print N newly generated puzzles
+             [--size B]                 with boxes of B, either N for N by N boxes or RxC for
+                                        boxes R cells tall and C wide, like 2x3 for 6x6 boards
+             [--seed S]                 the seed of every puzzle is printed to stderr
+             [--clues N]                stop removing clues once N are left
+             [--symmetry S]             one of none, rotational, diagonal, mirror or quarter
+             [--budget MS]              give up filling or stop removing clues after MS milliseconds
+             [--fill-steps N]           give up filling after N search steps
+             [--removals N]             stop after trying to remove clues N times
+             [--grade G]                one of easy, medium, hard or expert
+             [--jigsaw]                 replace the boxes with random irregular regions
+             [--variant V]              add the rules of V, a comma separated list of diagonal,
+                                        windoku, anti-knight or anti-king
+             [--to F]                   print the puzzles in format F
+    count-solutions <file> [--limit N]  print the number of solutions of every puzzle in <file>
+    validate <file>                     check that every puzzle in <file> has exactly one solution
+    rate <file> [--trace]               print the difficulty of every puzzle in <file>,
+                                        and with --trace every step needed to solve it
+    convert <file> --to F               print every puzzle in <file> in format F
+
+formats:
+    line    one puzzle per line, with '.' or '0' for empty cells
+    grid    one row per line, boxes may be separated with '|', '-' and '+'
+    sdk     SadMan single puzzle, '#' comment lines followed by one row per line
+    sdm     SadMan collection, one puzzle per line with '0' for empty cells
+
+numbers above 9 are written as letters starting from 'A'. boards in these formats get the usual
+boxes for their size, 2x3 for 6x6 and 3x4 for 12x12. jigsaw puzzles follow the cells of every
+line with the region of each cell, written like the numbers. every command that reads <file> takes
+--format F, otherwise the format is taken from the .sdk or .sdm extension or guessed from the
+contents. <file> can be '-' for stdin. variant rules are not stored in any format, so every
+command that reads <file> takes --variant V as well";
+
+fn main() -> Result<ExitCode> {
+    color_eyre::install()?;
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let Some((command, args)) = args.split_first() else {
+        eprintln!("{USAGE}");
+        return Ok(ExitCode::from(2));
+    };
+
+    match command.as_str() {
+        "solve" => solve(&read_puzzles(args)?, output_format(args)?),
+        "generate" => generate(
+            Generator {
+                target_clues: option(args, "--clues")?,
+                symmetry: symmetry(option(args, "--symmetry")?)?,
+                time_budget: option(args, "--budget")?.map(Duration::from_millis),
+                fill_steps: option(args, "--fill-steps")?,
+                max_removals: option(args, "--removals")?,
+                target_grade: option(args, "--grade")?.map(grade).transpose()?,
+                jigsaw: flag(args, "--jigsaw"),
+                ..Generator::new(
+                    option::<Shape>(args, "--size")?
+                        .unwrap_or_default()
+                        .with_variants(variants(option(args, "--variant")?)?),
+                    option(args, "--seed")?.unwrap_or_else(|| thread_rng().gen()),
+                )
+            },
+            option(args, "--count")?.unwrap_or(1),
+            output_format(args)?,
+        ),
+        "count-solutions" => count_solutions(&read_puzzles(args)?, option(args, "--limit")?),
+        "validate" => validate(&read_puzzles(args)?),
+        "rate" => rate(&read_puzzles(args)?, flag(args, "--trace")),
+        "convert" => {
+            let Some(format) = option(args, "--to")?.map(format).transpose()? else {
+                bail!("missing `--to` option\n\n{USAGE}");
+            };
+            convert(&read_puzzles(args)?, format)
+        }
+        "help" | "--help" | "-h" => {
+            println!("{USAGE}");
+            Ok(ExitCode::SUCCESS)
+        }
+        _ => {
+            eprintln!("unknown command `{command}`\n\n{USAGE}");
+            Ok(ExitCode::from(2))
+        }
+    }
+}
+
+// options that do not take a value
+const FLAGS: &[&str] = &["--trace", "--jigsaw"];
+
+fn positional(args: &[String]) -> Result<&str> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if FLAGS.contains(&arg.as_str()) {
+            continue;
+        } else if arg.starts_with("--") {
+            // skip the option value
+            iter.next();
+        } else {
+            return Ok(arg);
+        }
+    }
+    Err(eyre!("missing <file> argument\n\n{USAGE}"))
+}
+
+fn option<T>(args: &[String], name: &str) -> Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: Error + Send + Sync + 'static,
+{
+    let Some(index) = args.iter().position(|arg| arg == name) else {
+        return Ok(None);
+    };
+    let value = args
+        .get(index + 1)
+        .ok_or_else(|| eyre!("missing value for `{name}`"))?;
+    let value = value
+        .parse()
+        .wrap_err_with(|| format!("invalid value `{value}` for `{name}`"))?;
+    Ok(Some(value))
+}
+
+fn flag(args: &[String], name: &str) -> bool {
+    args.iter().any(|arg| arg == name)
+}
+
+fn grade(name: String) -> Result<Grade> {
+    Grade::ALL
+        .into_iter()
+        .find(|grade| grade.name() == name)
+        .ok_or_else(|| eyre!("unknown grade `{name}`"))
+}
+
+fn symmetry(name: Option<String>) -> Result<Symmetry> {
+    let Some(name) = name else {
+        return Ok(Symmetry::None);
+    };
+    Symmetry::ALL
+        .into_iter()
+        .find(|symmetry| symmetry.name() == name)
+        .ok_or_else(|| eyre!("unknown symmetry `{name}`"))
+}
+
+fn variants(names: Option<String>) -> Result<Vec<Variant>> {
+    let Some(names) = names else {
+        return Ok(vec![]);
+    };
+    names
+        .split(',')
+        .map(|name| {
+            Variant::ALL
+                .into_iter()
+                .find(|variant| variant.name() == name)
+                .ok_or_else(|| eyre!("unknown variant `{name}`"))
+        })
+        .collect()
+}
+
+fn format(name: String) -> Result<Format> {
+    Format::ALL
+        .into_iter()
+        .find(|format| format.name() == name)
+        .ok_or_else(|| eyre!("unknown format `{name}`"))
+}
+
+fn output_format(args: &[String]) -> Result<Format> {
+    Ok(option(args, "--to")?
+        .map(format)
+        .transpose()?
+        .unwrap_or(Format::Line))
+}
+
+// prints a puzzle, puzzles that span several lines are set apart by an empty line
+fn print(format: Format, shape: &Shape, choices: &[Choice]) {
+    match format {
+        Format::Line | Format::Sdm => println!("{}", format.write(shape, choices)),
+        Format::Grid | Format::Sdk => println!("{}\n", format.write(shape, choices)),
+    }
+}
+
+fn read_puzzles(args: &[String]) -> Result<Vec<ParsedPuzzle>> {
+    let path = positional(args)?;
+    let input = if path == "-" {
+        let mut input = String::new();
+        io::stdin().read_to_string(&mut input)?;
+        input
+    } else {
+        fs::read_to_string(path).wrap_err_with(|| format!("could not read `{path}`"))?
+    };
+
+    let format = match option(args, "--format")?.map(format).transpose()? {
+        Some(format) => format,
+        None => Path::new(path)
+            .extension()
+            .and_then(|extension| Format::from_extension(&extension.to_string_lossy()))
+            .unwrap_or_else(|| Format::detect(&input)),
+    };
+    let variants = variants(option(args, "--variant")?)?;
+    let puzzles = format
+        .parse(&input)
+        .wrap_err_with(|| format!("could not parse `{path}` as {}", format.name()))?;
+    Ok(puzzles
+        .into_iter()
+        .map(|puzzle| ParsedPuzzle {
+            shape: puzzle.shape.with_variants(variants.iter().copied()),
+            ..puzzle
+        })
+        .collect())
+}
+
+fn has_conflicts(shape: &Shape, givens: &[Choice]) -> bool {
+    !sudoku::conflicting(shape, givens).is_empty()
+}
+
+fn find_solutions(shape: &Shape, givens: &[Choice], limit: Option<usize>) -> Vec<Solution> {
+    let mut solutions = vec![];
+    if has_conflicts(shape, givens) {
+        return solutions;
+    }
+
+    Sudoku::new(shape, givens.iter().copied()).solve(|solution| {
+        solutions.push(solution);
+
+        if limit.is_some_and(|limit| solutions.len() >= limit) {
+            SolvingState::Abort
+        } else {
+            SolvingState::Continue
+        }
+    });
+    solutions
+}
+
+fn count(shape: &Shape, givens: &[Choice], limit: Option<usize>) -> usize {
+    if has_conflicts(shape, givens) {
+        return 0;
+    }
+    Sudoku::new(shape, givens.iter().copied()).count_solutions(limit)
+}
+
+fn solve(puzzles: &[ParsedPuzzle], format: Format) -> Result<ExitCode> {
+    let mut exit_code = ExitCode::SUCCESS;
+
+    for puzzle in puzzles {
+        let solutions = find_solutions(&puzzle.shape, &puzzle.givens, Some(2));
+        match solutions.as_slice() {
+            [solution] => {
+                let board: Vec<Choice> = puzzle.givens.iter().chain(solution).copied().collect();
+                print(format, &puzzle.shape, &board);
+            }
+            [] => {
+                eprintln!("line {}: puzzle has no solution", puzzle.line);
+                exit_code = ExitCode::FAILURE;
+            }
+            _ => {
+                eprintln!("line {}: puzzle has more than one solution", puzzle.line);
+                exit_code = ExitCode::FAILURE;
+            }
+        }
+    }
+    Ok(exit_code)
+}
+
+fn count_solutions(puzzles: &[ParsedPuzzle], limit: Option<usize>) -> Result<ExitCode> {
+    for puzzle in puzzles {
+        println!("{}", count(&puzzle.shape, &puzzle.givens, limit));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn validate(puzzles: &[ParsedPuzzle]) -> Result<ExitCode> {
+    let mut exit_code = ExitCode::SUCCESS;
+
+    for puzzle in puzzles {
+        let status = if has_conflicts(&puzzle.shape, &puzzle.givens) {
+            "invalid: givens break a rule of the board"
+        } else {
+            match count(&puzzle.shape, &puzzle.givens, Some(2)) {
+                0 => "invalid: no solution",
+                1 => "valid",
+                _ => "invalid: more than one solution",
+            }
+        };
+
+        if status != "valid" {
+            exit_code = ExitCode::FAILURE;
+        }
+        println!("line {}: {status}", puzzle.line);
+    }
+    Ok(exit_code)
+}
+
+fn rate(puzzles: &[ParsedPuzzle], trace: bool) -> Result<ExitCode> {
+    let mut exit_code = ExitCode::SUCCESS;
+
+    for puzzle in puzzles {
+        if has_conflicts(&puzzle.shape, &puzzle.givens) {
+            println!(
+                "line {}: invalid: givens break a rule of the board",
+                puzzle.line
+            );
+            exit_code = ExitCode::FAILURE;
+            continue;
+        }
+
+        let rating = logic::rate(&puzzle.shape, puzzle.givens.iter().copied());
+        match rating.grade() {
+            Some(grade) => println!("line {}: {}", puzzle.line, grade.name()),
+            None => println!("line {}: beyond expert", puzzle.line),
+        }
+        if trace {
+            for step in rating.steps {
+                println!("    {step}");
+            }
+        }
+    }
+    Ok(exit_code)
+}
+
+fn generate(generator: Generator, count: u64, format: Format) -> Result<ExitCode> {
+    for seed in (0..count).map(|index| generator.seed.wrapping_add(index)) {
+        eprintln!("seed {seed}");
+
+        let puzzle = match (Generator {
+            seed,
+            ..generator.clone()
+        })
+        .generate()
+        {
+            Ok(puzzle) => puzzle,
+            Err(error) => {
+                let size = generator.shape.size();
+                eprintln!("{size}x{size}: {error}");
+                return Ok(ExitCode::FAILURE);
+            }
+        };
+        print(format, &puzzle.shape, &puzzle.givens);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn convert(puzzles: &[ParsedPuzzle], format: Format) -> Result<ExitCode> {
+    for puzzle in puzzles {
+        print(format, &puzzle.shape, &puzzle.givens);
+    }
+    Ok(ExitCode::SUCCESS)
+}
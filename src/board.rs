@@ -0,0 +1,457 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_ecs_tilemap::{
+    helpers::geometry::get_tilemap_center_transform,
+    map::{TilemapGridSize, TilemapId, TilemapSize, TilemapTexture, TilemapTileSize, TilemapType},
+    tiles::{TileBundle, TileColor, TilePos, TileStorage, TileTextureIndex, TileVisible},
+    TilemapBundle,
+};
+use sudoku::{CellHouses, Choice, Shape};
+
+use crate::camera::CameraControl;
+
+use crate::hint::{Hint, ELIMINATION_COLOR, INVOLVED_COLOR, PLACEMENT_COLOR};
+use crate::win::{GameState, WIN_COLOR};
+
+// blank tile right after the digit glyphs
+pub const EMPTY_TILE: u32 = (26 * 4) + 10;
+const DIGIT_TILES: u32 = 26 * 4;
+const LETTER_TILES: u32 = 26 * 3;
+
+// size of a cell on screen before the camera zooms
+const CELL_SIZE: f32 = 32.;
+
+const ENTRY_COLOR: Color = Color::rgb(0.3, 0.4, 1.);
+const SELECTION_COLOR: Color = Color::rgb(0.6, 0.8, 1.);
+const CONFLICT_COLOR: Color = Color::rgb(1., 0.4, 0.4);
+// numbers in the extra houses of a variant, like the diagonals or windows
+const HOUSE_COLOR: Color = Color::rgb(1., 0.85, 0.55);
+
+#[derive(Component)]
+pub struct SudokuBoardFG;
+
+#[derive(Component)]
+pub struct SudokuBoardBG;
+
+// small candidate numbers, every cell is split into a square of sub-tiles, see note_pos
+#[derive(Component)]
+pub struct SudokuBoardNotes;
+
+// the atlas every tilemap of the board is drawn from
+#[derive(Resource)]
+pub struct TileTexture(pub Handle<Image>);
+
+// the puzzle being played, givens are locked and entries are placed by the player
+#[derive(Resource)]
+pub struct Board {
+    pub shape: Shape,
+    pub givens: Vec<Choice>,
+    pub entries: Vec<Choice>,
+    // candidates noted by the player as a bitmask per cell, indexed by row * size + column
+    pub notes: Vec<u32>,
+}
+
+// the cell the player is about to fill in, as (row, column)
+#[derive(Resource, Default)]
+pub struct Selection(pub Option<(u32, u32)>);
+
+impl Board {
+    pub fn new(shape: Shape, givens: Vec<Choice>) -> Self {
+        Self {
+            notes: vec![0; shape.cells() as usize],
+            shape,
+            givens,
+            entries: vec![],
+        }
+    }
+
+    pub fn size(&self) -> u32 {
+        self.shape.size()
+    }
+
+    // givens followed by the entries of the player
+    pub fn choices(&self) -> impl Iterator<Item = Choice> + '_ {
+        self.givens.iter().chain(self.entries.iter()).copied()
+    }
+
+    pub fn get(&self, row: u32, column: u32) -> Option<Choice> {
+        self.choices()
+            .find(|choice| choice.row == row && choice.column == column)
+    }
+
+    pub fn is_given(&self, row: u32, column: u32) -> bool {
+        self.givens
+            .iter()
+            .any(|choice| choice.row == row && choice.column == column)
+    }
+
+    // replaces whatever the player entered in the cell of the choice
+    pub fn place(&mut self, choice: Choice) {
+        self.clear(choice.row, choice.column);
+        self.entries.push(choice);
+
+        // the number can no longer go anywhere the placed one sees
+        for cell in self.notes_cleared_by(choice) {
+            self.notes[cell as usize] &= !(1 << choice.number);
+        }
+    }
+
+    // cells noting the number of the choice that placing it clears, indexed by row * size + column
+    pub fn notes_cleared_by(&self, choice: Choice) -> Vec<u32> {
+        let size = self.size();
+        let houses = CellHouses::new(&self.shape);
+        (0..size.pow(2))
+            .filter(|&cell| {
+                self.notes[cell as usize] & (1 << choice.number) != 0
+                    && Choice::new(&self.shape, cell / size, cell % size, choice.number)
+                        .conflicts_with(&self.shape, &houses, &choice)
+            })
+            .collect()
+    }
+
+    pub fn clear(&mut self, row: u32, column: u32) {
+        self.entries
+            .retain(|choice| choice.row != row || choice.column != column);
+    }
+
+    pub fn notes(&self, row: u32, column: u32) -> u32 {
+        self.notes[(row * self.size() + column) as usize]
+    }
+
+    pub fn toggle_note(&mut self, row: u32, column: u32, number: u32) {
+        let size = self.size();
+        self.notes[(row * size + column) as usize] ^= 1 << number;
+    }
+}
+
+// row 0 is drawn at the top of the tilemap
+pub fn tile_pos(size: u32, row: u32, column: u32) -> TilePos {
+    TilePos {
+        x: column,
+        y: size - 1 - row,
+    }
+}
+
+pub fn cell_at(size: u32, pos: &TilePos) -> (u32, u32) {
+    (size - 1 - pos.y, pos.x)
+}
+
+// sub-tiles across a cell of the notes tilemap, enough for every number of the board
+pub fn notes_per_side(shape: &Shape) -> u32 {
+    shape.box_rows.max(shape.box_columns)
+}
+
+// sub-tile of the notes tilemap showing number as a candidate of the cell,
+// candidates are laid out left to right, top to bottom
+pub fn note_pos(shape: &Shape, row: u32, column: u32, number: u32) -> TilePos {
+    let side = notes_per_side(shape);
+    let cell = tile_pos(shape.size(), row, column);
+    TilePos {
+        x: cell.x * side + number % side,
+        y: cell.y * side + (side - 1 - number / side),
+    }
+}
+
+// numbers are written 1 to 9 and then A to Z, like in the text formats
+pub fn glyph(number: u32) -> u32 {
+    if number < 9 {
+        DIGIT_TILES + number
+    } else {
+        LETTER_TILES + number - 9
+    }
+}
+
+// the frame is drawn from the tiles of a 3 by 3 box outline, a cell can need a border on
+// both sides in a jigsaw, so one layer draws the left and top borders and one the others
+#[derive(Clone, Copy)]
+enum FrameLayer {
+    TopLeft,
+    BottomRight,
+}
+
+// a border is drawn where the neighbouring cell lies in another box or region
+fn frame_tile(shape: &Shape, pos: TilePos, layer: FrameLayer) -> u32 {
+    let size = shape.size();
+    let (row, column) = cell_at(size, &pos);
+    let square = shape.square_of(row, column);
+    let border = |row: Option<u32>, column: Option<u32>| match (row, column) {
+        (Some(row), Some(column)) if row < size && column < size => {
+            shape.square_of(row, column) != square
+        }
+        _ => true,
+    };
+
+    let (x, y) = match layer {
+        FrameLayer::TopLeft => (
+            if border(Some(row), column.checked_sub(1)) {
+                0
+            } else {
+                1
+            },
+            if border(row.checked_sub(1), Some(column)) {
+                2
+            } else {
+                1
+            },
+        ),
+        FrameLayer::BottomRight => (
+            if border(Some(row), Some(column + 1)) {
+                2
+            } else {
+                1
+            },
+            if border(Some(row + 1), Some(column)) {
+                0
+            } else {
+                1
+            },
+        ),
+    };
+    2 * 26 + x - y * 26
+}
+
+fn spawn_tilemap(
+    commands: &mut Commands,
+    texture: &Handle<Image>,
+    size: TilemapSize,
+    tile_size: f32,
+    z: f32,
+    tile: impl Fn(TilePos) -> TileBundle,
+) -> Entity {
+    let tilemap_entity = commands.spawn_empty().id();
+    let mut tile_storage = TileStorage::empty(size);
+
+    for x in 0..size.x {
+        for y in 0..size.y {
+            let position = TilePos { x, y };
+            let tile_entity = commands
+                .spawn(TileBundle {
+                    position,
+                    tilemap_id: TilemapId(tilemap_entity),
+                    ..tile(position)
+                })
+                .id();
+            tile_storage.set(&position, tile_entity);
+        }
+    }
+
+    let grid_size = TilemapGridSize::new(tile_size, tile_size);
+    let map_type = TilemapType::default();
+    commands.entity(tilemap_entity).insert(TilemapBundle {
+        grid_size,
+        size,
+        map_type,
+        texture: TilemapTexture::Single(texture.clone()),
+        tile_size: TilemapTileSize::new(tile_size, tile_size),
+        storage: tile_storage,
+        transform: get_tilemap_center_transform(&size, &grid_size, &map_type, z),
+        ..Default::default()
+    });
+    tilemap_entity
+}
+
+// the tilemaps are made for one board shape, so they are replaced when the shape changes
+pub fn rebuild_tilemaps(
+    mut commands: Commands,
+    board: Res<Board>,
+    texture: Res<TileTexture>,
+    tilemap_query: Query<(Entity, &TileStorage)>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut camera_control: ResMut<CameraControl>,
+    mut built_shape: Local<Option<Shape>>,
+) {
+    let shape = &board.shape;
+    let size = board.size();
+    if built_shape.as_ref() == Some(shape) {
+        return;
+    }
+    *built_shape = Some(shape.clone());
+
+    for (tilemap_entity, tile_storage) in tilemap_query.iter() {
+        for tile_entity in tile_storage.iter().flatten() {
+            commands.entity(*tile_entity).despawn();
+        }
+        commands.entity(tilemap_entity).despawn();
+    }
+
+    let board_size = TilemapSize::new(size, size);
+    let board_fg = spawn_tilemap(&mut commands, &texture.0, board_size, CELL_SIZE, 0., |_| {
+        TileBundle {
+            texture_index: TileTextureIndex(EMPTY_TILE),
+            ..default()
+        }
+    });
+    commands.entity(board_fg).insert(SudokuBoardFG);
+
+    for layer in [FrameLayer::TopLeft, FrameLayer::BottomRight] {
+        let board_bg = spawn_tilemap(
+            &mut commands,
+            &texture.0,
+            board_size,
+            CELL_SIZE,
+            1.,
+            |pos| TileBundle {
+                texture_index: TileTextureIndex(frame_tile(shape, pos, layer)),
+                ..default()
+            },
+        );
+        commands.entity(board_bg).insert(SudokuBoardBG);
+    }
+
+    // every cell holds a square of notes, so the notes tilemap covers the same area as the board,
+    // it is drawn over the digits but under the frame
+    let side = notes_per_side(shape);
+    let board_notes = spawn_tilemap(
+        &mut commands,
+        &texture.0,
+        TilemapSize::new(size * side, size * side),
+        CELL_SIZE / side as f32,
+        0.5,
+        |_| TileBundle {
+            texture_index: TileTextureIndex(EMPTY_TILE),
+            visible: TileVisible(false),
+            ..default()
+        },
+    );
+    commands.entity(board_notes).insert(SudokuBoardNotes);
+
+    // zoom so the whole board fits in the window with some room to spare
+    if let Ok(window) = window_query.get_single() {
+        let fit = window.width().min(window.height()) * 0.8;
+        camera_control.target_pos = Vec3::ZERO;
+        camera_control.target_scale = size as f32 * CELL_SIZE / fit;
+    }
+}
+
+pub fn draw_digits(
+    board: Res<Board>,
+    board_fg_query: Query<&TileStorage, With<SudokuBoardFG>>,
+    mut tile_query: Query<&mut TileTextureIndex>,
+) {
+    let Ok(tile_storage) = board_fg_query.get_single() else {
+        return;
+    };
+
+    let size = board.size();
+    for row in 0..size {
+        for column in 0..size {
+            let Some(tile_entity) = tile_storage.get(&tile_pos(size, row, column)) else {
+                continue;
+            };
+            if let Ok(mut tile) = tile_query.get_mut(tile_entity) {
+                tile.0 = match board.get(row, column) {
+                    Some(choice) => glyph(choice.number),
+                    None => EMPTY_TILE,
+                };
+            }
+        }
+    }
+}
+
+// notes are only shown in empty cells, they stay stored while the cell is filled
+pub fn draw_notes(
+    board: Res<Board>,
+    board_notes_query: Query<&TileStorage, With<SudokuBoardNotes>>,
+    mut tile_query: Query<(&mut TileTextureIndex, &mut TileVisible)>,
+) {
+    let Ok(tile_storage) = board_notes_query.get_single() else {
+        return;
+    };
+
+    let size = board.size();
+    for row in 0..size {
+        for column in 0..size {
+            let empty = board.get(row, column).is_none();
+            let notes = board.notes(row, column);
+            for number in 0..size {
+                let Some(tile_entity) =
+                    tile_storage.get(&note_pos(&board.shape, row, column, number))
+                else {
+                    continue;
+                };
+                if let Ok((mut tile, mut visible)) = tile_query.get_mut(tile_entity) {
+                    tile.0 = glyph(number);
+                    visible.0 = empty && notes & (1 << number) != 0;
+                }
+            }
+        }
+    }
+}
+
+// tints every cell, a hint is drawn over the selection, then conflicts, the entries and the
+// houses of variants
+pub fn paint_cells(
+    board: Res<Board>,
+    selection: Res<Selection>,
+    hint: Res<Hint>,
+    state: Res<State<GameState>>,
+    board_fg_query: Query<&TileStorage, With<SudokuBoardFG>>,
+    board_notes_query: Query<&TileStorage, With<SudokuBoardNotes>>,
+    mut tile_query: Query<&mut TileColor>,
+) {
+    let (Ok(fg_storage), Ok(notes_storage)) =
+        (board_fg_query.get_single(), board_notes_query.get_single())
+    else {
+        return;
+    };
+    if !(board.is_changed() || selection.is_changed() || hint.is_changed() || state.is_changed()) {
+        return;
+    }
+
+    let size = board.size();
+    let mut colors = vec![Color::WHITE; size.pow(2) as usize];
+    // the selection and hint of a bigger board may be left over for a frame after a new one loads
+    let mut paint = |row: u32, column: u32, color: Color| {
+        if row < size && column < size {
+            colors[(row * size + column) as usize] = color;
+        }
+    };
+
+    for variant in board.shape.variants.iter() {
+        for &(row, column) in variant.houses(&board.shape).iter().flatten() {
+            paint(row, column, HOUSE_COLOR);
+        }
+    }
+    // entries of the player are tinted so they can be told apart from the givens
+    for choice in board.entries.iter() {
+        paint(choice.row, choice.column, ENTRY_COLOR);
+    }
+    // givens can break a rule too once the player entered a number next to them
+    for choice in sudoku::conflicting(&board.shape, &board.choices().collect::<Vec<_>>()) {
+        paint(choice.row, choice.column, CONFLICT_COLOR);
+    }
+    if let Some((row, column)) = selection.0 {
+        paint(row, column, SELECTION_COLOR);
+    }
+    if let Some(step) = &hint.0 {
+        for &(row, column) in step.cells.iter() {
+            paint(row, column, INVOLVED_COLOR);
+        }
+        for choice in step.eliminations.iter() {
+            paint(choice.row, choice.column, ELIMINATION_COLOR);
+        }
+        if let Some(choice) = step.placement {
+            paint(choice.row, choice.column, PLACEMENT_COLOR);
+        }
+    }
+    if *state.get() == GameState::Won {
+        colors.fill(WIN_COLOR);
+    }
+
+    // the frame tilemaps are drawn over the cells and are transparent apart from their lines, and
+    // the digit and blank tiles of the atlas are opaque, so a tile behind a cell would never show
+    // and the cell is tinted through its own tiles instead
+    for row in 0..size {
+        for column in 0..size {
+            let color = colors[(row * size + column) as usize];
+            let tiles = std::iter::once(fg_storage.get(&tile_pos(size, row, column))).chain(
+                (0..size)
+                    .map(|number| notes_storage.get(&note_pos(&board.shape, row, column, number))),
+            );
+            for tile_entity in tiles.flatten() {
+                if let Ok(mut tile_color) = tile_query.get_mut(tile_entity) {
+                    tile_color.0 = color;
+                }
+            }
+        }
+    }
+}
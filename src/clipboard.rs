@@ -0,0 +1,76 @@
+use arboard::Clipboard;
+use bevy::prelude::*;
+use sudoku::{Choice, Format, ParsedPuzzle, Sudoku};
+
+use crate::board::Board;
+use crate::history::is_control_pressed;
+use crate::packs::{LoadPuzzle, PuzzleSource};
+
+// messages of the clipboard and the menu, shown in the bottom right corner
+#[derive(Component)]
+pub struct MessageText;
+
+// Ctrl+C copies the grid on screen, Ctrl+V replaces the board with the puzzle on the clipboard
+pub fn copy_paste(
+    input_keyboard: Res<ButtonInput<KeyCode>>,
+    board: Res<Board>,
+    mut events: EventWriter<LoadPuzzle>,
+    mut query: Query<&mut Text, With<MessageText>>,
+) {
+    if !is_control_pressed(&input_keyboard) {
+        return;
+    }
+
+    let message = if input_keyboard.just_pressed(KeyCode::KeyC) {
+        let choices: Vec<Choice> = board.choices().collect();
+        match Clipboard::new().and_then(|mut clipboard| {
+            clipboard.set_text(Format::Line.write(&board.shape, &choices))
+        }) {
+            Ok(()) => "Copied the grid".to_string(),
+            Err(error) => format!("Could not copy the grid: {error}"),
+        }
+    } else if input_keyboard.just_pressed(KeyCode::KeyV) {
+        match Clipboard::new()
+            .and_then(|mut clipboard| clipboard.get_text())
+            .map_err(|error| error.to_string())
+            .and_then(|text| read_puzzle(&text))
+        {
+            Ok(puzzle) => {
+                events.send(LoadPuzzle {
+                    shape: puzzle.shape,
+                    givens: puzzle.givens,
+                    source: PuzzleSource::Pasted,
+                });
+                "Pasted a new puzzle".to_string()
+            }
+            Err(error) => format!("Could not paste the puzzle: {error}"),
+        }
+    } else {
+        return;
+    };
+
+    if let Ok(mut text) = query.get_single_mut() {
+        text.sections[0].value = message;
+    }
+}
+
+// only puzzles with exactly one solution are accepted
+fn read_puzzle(text: &str) -> Result<ParsedPuzzle, String> {
+    let format = Format::detect(text);
+    let puzzle = format
+        .parse(text)
+        .map_err(|error| error.to_string())?
+        .into_iter()
+        .next()
+        .ok_or("the clipboard holds no puzzle")?;
+
+    if !sudoku::conflicting(&puzzle.shape, &puzzle.givens).is_empty() {
+        return Err("some numbers break a row, column or box rule".into());
+    }
+
+    match Sudoku::new(&puzzle.shape, puzzle.givens.iter().copied()).count_solutions(Some(2)) {
+        0 => Err("the puzzle has no solution".into()),
+        1 => Ok(puzzle),
+        _ => Err("the puzzle has more than one solution".into()),
+    }
+}
@@ -1,6 +1,6 @@
 use crate::dancing_links::{DoublyLinkedList, Indexed};
 
-use std::{fmt, ops};
+use std::{fmt, ops, time::Instant};
 
 #[derive(Default, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Cell(usize);
@@ -13,16 +13,40 @@ pub struct Matrix {
     pub column_headers: Vec<Cell>,
     pub column_sizes: Vec<usize>,
     pub row_bounds: Vec<(Cell, Cell)>,
+    // index of the row every cell belongs to, None for the root and the column headers
+    pub row_ids: Vec<Option<usize>>,
 
     partial_solution: Vec<Cell>,
+    // first cells of the rows picked ahead of the search, see select_row
+    selected_rows: Vec<Cell>,
+    // columns covered by those rows, indexed like the column sizes
+    selected_columns: Vec<bool>,
+    // the search gives up once this runs out, see set_limit
+    limit: SearchLimit,
+    // partial solutions visited by the current search
+    steps: u64,
+    gave_up: bool,
 
     solving_state: SolvingState,
 }
 
+// bounds a search, which stops as if the callback aborted once either runs out
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SearchLimit {
+    pub deadline: Option<Instant>,
+    // partial solutions the search may visit, unlike the deadline this gives up at the same
+    // point on every machine
+    pub steps: Option<u64>,
+}
+
 #[derive(Debug, Clone)]
 pub struct MatrixSize {
+    // primary columns, every solution covers each of them exactly once
     pub x: usize,
     pub y: usize,
+    // columns after the primary ones that a solution covers at most once,
+    // the solver never branches on them
+    pub secondary: usize,
 }
 
 impl Indexed for Cell {
@@ -70,17 +94,24 @@ pub enum SolvingState {
 
 pub const H: Cell = Cell(0);
 
-impl<'a> Matrix {
+impl Matrix {
     pub fn new(size: MatrixSize) -> Self {
+        let columns = size.x + size.secondary;
         let mut ret = Self {
-            x: DoublyLinkedList::with_capacity(size.x + 1),
+            x: DoublyLinkedList::with_capacity(columns + 1),
             y: DoublyLinkedList::with_capacity(size.y + 1),
 
-            column_headers: Vec::with_capacity(size.x + 1),
-            column_sizes: Vec::with_capacity(size.x + 1),
+            column_headers: Vec::with_capacity(columns + 1),
+            column_sizes: Vec::with_capacity(columns + 1),
             row_bounds: Vec::with_capacity(size.y + 1),
+            row_ids: Vec::with_capacity(columns + 1),
 
             partial_solution: Vec::new(),
+            selected_rows: Vec::new(),
+            selected_columns: vec![false; columns + 1],
+            limit: SearchLimit::default(),
+            steps: 0,
+            gave_up: false,
 
             solving_state: SolvingState::Continue,
         };
@@ -92,6 +123,11 @@ impl<'a> Matrix {
         for _ in 0..size.x {
             ret.add_column();
         }
+        for _ in 0..size.secondary {
+            // secondary columns stay out of the header row so the solver never picks them,
+            // covering one still removes the rows that share it
+            ret.alloc_column();
+        }
         ret
     }
 
@@ -109,6 +145,7 @@ impl<'a> Matrix {
         // keep track of the column header index and column size
         self.column_headers.push(cell);
         self.column_sizes.push(0);
+        self.row_ids.push(None);
 
         cell
     }
@@ -124,37 +161,56 @@ impl<'a> Matrix {
         // row length should be the number of column headers wihtout the root column
         assert_eq!(row.len(), self.column_sizes.len() - 1);
 
-        let mut column_header = H;
+        let columns: Vec<usize> = row
+            .iter()
+            .enumerate()
+            .filter(|(_, &is_one)| is_one)
+            .map(|(index, _)| index)
+            .collect();
+        self.add_row_sparse(&columns);
+    }
+
+    // adds a row with one's only in the given columns, in time proportional to their count
+    pub fn add_row_sparse(&mut self, columns: &[usize]) {
+        let mut columns = columns.to_vec();
+        columns.sort_unstable();
+        columns.dedup();
+
         let mut prev_cell = None;
         let mut row_start = None;
         let mut row_end = None;
 
-        for &is_one in row {
-            column_header = self.x[column_header].next;
-
-            if is_one {
-                // set the column header for a curent cell
-                self.column_headers.push(column_header);
-
-                // increment column size
-                self.column_sizes[column_header] += 1;
-
-                // first cell is linked to itself
-                let cell = self.alloc_cell();
-
-                if let Some(prev_cell) = prev_cell {
-                    // if it is not the first cell in this row, we insert it after the previous
-                    self.x.insert(prev_cell, cell);
-                } else {
-                    row_start = Some(cell);
-                    prev_cell = Some(cell);
-                }
+        for index in columns {
+            // column sizes hold one entry per column header, including the root column
+            assert!(
+                index < self.column_sizes.len() - 1,
+                "column {index} is out of bounds"
+            );
+            // columns are allocated right after the root, before any row
+            let column_header = Cell(index + 1);
+
+            // set the column header for a curent cell
+            self.column_headers.push(column_header);
+
+            // increment column size
+            self.column_sizes[column_header] += 1;
+
+            // first cell is linked to itself
+            let cell = self.alloc_cell();
+            self.row_ids.push(Some(self.row_bounds.len()));
+
+            if let Some(prev_cell) = prev_cell {
+                // if it is not the first cell in this row, we insert it after the previous
+                self.x.insert(prev_cell, cell);
+            } else {
+                row_start = Some(cell);
+                prev_cell = Some(cell);
+            }
 
-                row_end = Some(cell);
+            row_end = Some(cell);
 
-                // insert the new cell at the end of the column
-                self.y.insert(self.y[column_header].prev, cell);
-            }
+            // insert the new cell at the end of the column
+            self.y.insert(self.y[column_header].prev, cell);
         }
         self.row_bounds.push((
             row_start.expect("rows cannot be empty"),
@@ -203,9 +259,105 @@ impl<'a> Matrix {
         self.x.restore(column);
     }
 
+    // covers the columns of a row as if the search had picked it, so every following solve only
+    // looks for the rest of a solution, which leaves the row out. returns false and changes
+    // nothing when an already selected row shares a column with it
+    pub fn select_row(&mut self, row: usize) -> bool {
+        let (start, _) = self.row_bounds[row];
+
+        let mut cells = vec![start];
+        let mut iter_columns = self.x.iter(start);
+        while let Some(cell) = iter_columns.next(&self.x) {
+            cells.push(cell);
+        }
+        if cells
+            .iter()
+            .any(|&cell| self.selected_columns[self.column_headers[cell].get_index()])
+        {
+            return false;
+        }
+
+        // the same order the search covers the columns of a row it picks
+        for cell in cells {
+            let column = self.column_headers[cell];
+            self.selected_columns[column.get_index()] = true;
+            self.cover_column(column);
+        }
+        self.selected_rows.push(start);
+        true
+    }
+
+    // undoes every select_row, last one first
+    pub fn deselect_rows(&mut self) {
+        while let Some(start) = self.selected_rows.pop() {
+            let mut iter_columns = self.x.iter(start);
+            while let Some(cell) = iter_columns.prev(&self.x) {
+                let column = self.column_headers[cell];
+                self.selected_columns[column.get_index()] = false;
+                self.uncover_column(column);
+            }
+            let column = self.column_headers[start];
+            self.selected_columns[column.get_index()] = false;
+            self.uncover_column(column);
+        }
+    }
+
+    // bounds every following search, even between two solutions
+    pub fn set_limit(&mut self, limit: SearchLimit) {
+        self.limit = limit;
+    }
+
+    // whether the last search stopped because it ran out of its limit
+    pub fn gave_up(&self) -> bool {
+        self.gave_up
+    }
+
+    // calls back with every solution until the callback aborts, the matrix can be solved again
     pub fn solve<F>(&mut self, callback: &mut F)
     where
         F: FnMut(Solution) -> SolvingState,
+    {
+        self.start_search();
+        self.search(&mut |matrix: &Matrix| callback(matrix.partial_rows()));
+    }
+
+    // counts the solutions without building them, stopping once the limit is reached
+    pub fn count_solutions(&mut self, limit: Option<usize>) -> usize {
+        // the search only checks the limit after a solution, so it would already count one
+        if limit == Some(0) {
+            return 0;
+        }
+        let mut count = 0;
+        self.start_search();
+        self.search(&mut |_: &Matrix| {
+            count += 1;
+            if limit.is_some_and(|limit| count >= limit) {
+                SolvingState::Abort
+            } else {
+                SolvingState::Continue
+            }
+        });
+        count
+    }
+
+    fn start_search(&mut self) {
+        self.solving_state = SolvingState::Continue;
+        self.steps = 0;
+        self.gave_up = false;
+    }
+
+    // indices of the rows in the current partial solution
+    fn partial_rows(&self) -> Solution {
+        self.partial_solution
+            .iter()
+            .map(|cell| self.row_ids[cell.get_index()].expect("only row cells are picked"))
+            .collect()
+    }
+
+    // calls back at every leaf where all primary columns are covered
+    fn search<F>(&mut self, on_solution: &mut F)
+    where
+        F: FnMut(&Matrix) -> SolvingState,
     {
         match self.solving_state {
             SolvingState::Continue => {}
@@ -213,23 +365,24 @@ impl<'a> Matrix {
                 return;
             }
         }
+        self.steps += 1;
+        if self.limit.steps.is_some_and(|steps| self.steps > steps)
+            || self
+                .limit
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            self.gave_up = true;
+            self.solving_state = SolvingState::Abort;
+            return;
+        }
         // choose a collumn with the least amount of one's
         let mut iter = self.x.iter(H);
         let mut smallest_column = match iter.next(&self.x) {
             Some(cell) => cell,
             None => {
                 // if there are no columns, the current partial_solution is correct
-                let mut solution = vec![];
-
-                for row in self.partial_solution.iter() {
-                    for (index, (start, end)) in self.row_bounds.iter().enumerate() {
-                        if row >= start && row <= end {
-                            solution.push(index);
-                        }
-                    }
-                }
-
-                self.solving_state = callback(solution);
+                self.solving_state = on_solution(self);
                 // println!("solution found");
                 return;
             }
@@ -261,7 +414,7 @@ impl<'a> Matrix {
             }
 
             // RECURR
-            self.solve(callback);
+            self.search(on_solution);
 
             // UNWINDING
 
@@ -317,3 +470,144 @@ impl fmt::Display for Matrix {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(primary: usize, secondary: usize, rows: &[&[usize]]) -> Matrix {
+        let mut matrix = Matrix::new(MatrixSize {
+            x: primary,
+            y: rows.len(),
+            secondary,
+        });
+        for row in rows {
+            matrix.add_row_sparse(row);
+        }
+        matrix
+    }
+
+    // nothing covers the second column
+    fn no_solution() -> Matrix {
+        build(2, 0, &[&[0], &[0]])
+    }
+
+    // the example of Knuth's dancing links paper, only rows 0, 3 and 4 cover every column
+    fn one_solution() -> Matrix {
+        build(
+            7,
+            0,
+            &[
+                &[2, 4, 5],
+                &[0, 3, 6],
+                &[1, 2, 5],
+                &[0, 3],
+                &[1, 6],
+                &[3, 4, 6],
+            ],
+        )
+    }
+
+    // rows 0 and 1 together, row 2 alone and row 3 alone
+    fn three_solutions() -> Matrix {
+        build(2, 0, &[&[0], &[1], &[0, 1], &[0, 1]])
+    }
+
+    fn solutions(matrix: &mut Matrix) -> Vec<Solution> {
+        let mut solutions = vec![];
+        matrix.solve(&mut |mut solution: Solution| {
+            solution.sort_unstable();
+            solutions.push(solution);
+            SolvingState::Continue
+        });
+        solutions.sort();
+        solutions
+    }
+
+    #[test]
+    fn solve_finds_every_solution() {
+        assert_eq!(solutions(&mut no_solution()), Vec::<Solution>::new());
+        assert_eq!(solutions(&mut one_solution()), vec![vec![0, 3, 4]]);
+        assert_eq!(
+            solutions(&mut three_solutions()),
+            vec![vec![0, 1], vec![2], vec![3]]
+        );
+    }
+
+    #[test]
+    fn count_solutions_stops_at_the_limit() {
+        for (mut matrix, solutions) in [
+            (no_solution(), 0),
+            (one_solution(), 1),
+            (three_solutions(), 3),
+        ] {
+            assert_eq!(matrix.count_solutions(None), solutions);
+            assert_eq!(matrix.count_solutions(Some(0)), 0);
+            assert_eq!(matrix.count_solutions(Some(1)), solutions.min(1));
+            assert_eq!(matrix.count_solutions(Some(2)), solutions.min(2));
+            // counting leaves the matrix as it was
+            assert_eq!(matrix.count_solutions(None), solutions);
+        }
+    }
+
+    #[test]
+    fn selected_rows_are_left_out_of_the_solutions() {
+        let mut matrix = three_solutions();
+        assert!(matrix.select_row(0));
+        // row 2 shares the first column with row 0
+        assert!(!matrix.select_row(2));
+        assert_eq!(solutions(&mut matrix), vec![vec![1]]);
+        matrix.deselect_rows();
+        assert_eq!(matrix.count_solutions(None), 3);
+    }
+
+    #[test]
+    fn secondary_columns_are_never_required() {
+        // row 1 only covers the secondary column, so no solution needs it
+        let mut matrix = build(1, 1, &[&[0], &[1]]);
+        assert_eq!(solutions(&mut matrix), vec![vec![0]]);
+        // nothing covers the secondary column at all
+        let mut matrix = build(2, 1, &[&[0], &[1]]);
+        assert_eq!(solutions(&mut matrix), vec![vec![0, 1]]);
+    }
+
+    #[test]
+    fn secondary_columns_are_covered_at_most_once() {
+        // rows 0 and 1 share the secondary column 2, rows 2 and 3 leave it out
+        let mut matrix = build(2, 1, &[&[0, 2], &[1, 2], &[0], &[1]]);
+        assert_eq!(
+            solutions(&mut matrix),
+            vec![vec![0, 3], vec![1, 2], vec![2, 3]]
+        );
+    }
+
+    #[test]
+    fn a_search_out_of_its_limit_gives_up() {
+        let mut matrix = three_solutions();
+        matrix.set_limit(SearchLimit {
+            deadline: None,
+            steps: Some(0),
+        });
+        assert_eq!(matrix.count_solutions(None), 0);
+        assert!(matrix.gave_up());
+
+        matrix.set_limit(SearchLimit {
+            deadline: Some(Instant::now()),
+            steps: None,
+        });
+        assert_eq!(solutions(&mut matrix), Vec::<Solution>::new());
+        assert!(matrix.gave_up());
+
+        // enough steps to visit every partial solution
+        matrix.set_limit(SearchLimit {
+            deadline: None,
+            steps: Some(100),
+        });
+        assert_eq!(matrix.count_solutions(None), 3);
+        assert!(!matrix.gave_up());
+
+        matrix.set_limit(SearchLimit::default());
+        assert_eq!(matrix.count_solutions(None), 3);
+        assert!(!matrix.gave_up());
+    }
+}
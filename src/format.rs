@@ -0,0 +1,560 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use itertools::Itertools;
+
+use crate::sudoku::{Choice, Shape, MAX_SIZE};
+
+// text formats puzzles are read from and written in, numbers above 9 are written as letters,
+// jigsaw puzzles follow the cells of every line with the regions of those cells
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    // one puzzle per line, '.' or '0' for empty cells
+    Line,
+    // one row per line, boxes may be set apart with '|', '-' and '+'
+    Grid,
+    // SadMan Software single puzzle, '#' metadata lines followed by one row per line
+    Sdk,
+    // SadMan Software collection, one puzzle per line with '0' for empty cells
+    Sdm,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParsedPuzzle {
+    // line of the input the puzzle starts on, counted from 1
+    pub line: usize,
+    // boards of the text formats get the usual boxes for their size unless they list regions
+    pub shape: Shape,
+    pub givens: Vec<Choice>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseError {
+    // counted from 1
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl Format {
+    pub const ALL: [Format; 4] = [Format::Line, Format::Grid, Format::Sdk, Format::Sdm];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Line => "line",
+            Format::Grid => "grid",
+            Format::Sdk => "sdk",
+            Format::Sdm => "sdm",
+        }
+    }
+
+    pub fn from_extension(extension: &str) -> Option<Format> {
+        match extension.to_ascii_lowercase().as_str() {
+            "sdk" => Some(Format::Sdk),
+            "sdm" => Some(Format::Sdm),
+            _ => None,
+        }
+    }
+
+    // puzzles spread over several lines are grids, anything else is a puzzle per line
+    pub fn detect(text: &str) -> Format {
+        let Some(rows) = blocks(text).into_iter().next() else {
+            return Format::Line;
+        };
+        let rows: Vec<usize> = rows.iter().map(|(_, line)| cells(line).count()).collect();
+        let total: usize = rows.iter().sum();
+
+        // the rows of a jigsaw grid hold the regions as well, twice as many symbols
+        let is_grid = board_shape(total).is_some() && rows[0].pow(2) == total;
+        let is_jigsaw_grid =
+            total % 2 == 0 && board_shape(total / 2).is_some() && rows[0].pow(2) == 2 * total;
+        if rows.len() > 1 && (is_grid || is_jigsaw_grid) {
+            Format::Grid
+        } else {
+            Format::Line
+        }
+    }
+
+    pub fn parse(&self, text: &str) -> Result<Vec<ParsedPuzzle>, ParseError> {
+        match self {
+            Format::Line | Format::Sdm => blocks(text)
+                .into_iter()
+                .flatten()
+                .map(|line| parse_cells([line]))
+                .collect(),
+            Format::Grid => blocks(text).into_iter().map(parse_cells).collect(),
+            // later sections of a .sdk file hold the state of a game, not another puzzle
+            Format::Sdk => blocks(text).into_iter().take(1).map(parse_cells).collect(),
+        }
+    }
+
+    pub fn write(&self, shape: &Shape, choices: &[Choice]) -> String {
+        let size = shape.size() as usize;
+        let (box_rows, box_columns) = (shape.box_rows as usize, shape.box_columns as usize);
+        let blank = if *self == Format::Sdm { '0' } else { '.' };
+
+        let mut cells = vec![blank; size.pow(2)];
+        for choice in choices {
+            cells[choice.row as usize * size + choice.column as usize] = symbol(choice.number);
+        }
+
+        let regions: Option<Vec<char>> = shape
+            .regions
+            .as_ref()
+            .map(|regions| regions.iter().map(|&region| symbol(region)).collect());
+        let line = |start: usize, length: usize| {
+            let mut line: String = cells[start..start + length].iter().collect();
+            if let Some(regions) = &regions {
+                line.push(' ');
+                line.extend(&regions[start..start + length]);
+            }
+            line
+        };
+        let rows = || (0..size).map(|row| line(row * size, size)).join("\n");
+
+        match self {
+            Format::Line | Format::Sdm => line(0, size.pow(2)),
+            Format::Sdk => rows(),
+            // the regions of a jigsaw take the place of the box separators
+            Format::Grid if regions.is_some() => rows(),
+            Format::Grid => {
+                // a row of boxes holds box_rows boxes
+                let separator = vec!["-".repeat(box_columns); box_rows].join("+");
+                cells
+                    .chunks(size)
+                    .map(|row| {
+                        row.chunks(box_columns)
+                            .map(|cells| cells.iter().collect::<String>())
+                            .join("|")
+                    })
+                    .chunks(box_rows)
+                    .into_iter()
+                    .map(|rows| rows.collect::<Vec<_>>().join("\n"))
+                    .join(&format!("\n{separator}\n"))
+            }
+        }
+    }
+}
+
+// numbers are written 1 to 9 and then A to Z
+pub fn symbol(number: u32) -> char {
+    char::from_digit(number + 1, 36)
+        .expect("number should fit in a single symbol")
+        .to_ascii_uppercase()
+}
+
+// lines with cells in them, grouped into blocks that are set apart by empty lines or
+// '[' section headers of a .sdk file, '#' starts a comment
+fn blocks(text: &str) -> Vec<Vec<(usize, &str)>> {
+    let mut blocks = vec![vec![]];
+    for (index, line) in text.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('[') {
+            blocks.push(vec![]);
+        } else if !trimmed.starts_with('#') && cells(line).next().is_some() {
+            blocks.last_mut().unwrap().push((index + 1, line));
+        }
+    }
+    blocks.retain(|block| !block.is_empty());
+    blocks
+}
+
+// symbols of the cells with their column, whitespace and box separators are skipped
+fn cells(line: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    line.chars()
+        .enumerate()
+        .map(|(index, symbol)| (index + 1, symbol))
+        .filter(|(_, symbol)| !(symbol.is_whitespace() || matches!(symbol, '|' | '-' | '+')))
+}
+
+fn board_shape(cells: usize) -> Option<Shape> {
+    (1..=MAX_SIZE)
+        .filter_map(Shape::for_size)
+        .find(|shape| shape.cells() as usize == cells)
+}
+
+// a symbol with the line and column it was found at
+type Located = (usize, usize, char);
+
+fn parse_cells<'a>(
+    lines: impl IntoIterator<Item = (usize, &'a str)>,
+) -> Result<ParsedPuzzle, ParseError> {
+    let lines: Vec<Vec<Located>> = lines
+        .into_iter()
+        .map(|(line, text)| {
+            cells(text)
+                .map(|(column, symbol)| (line, column, symbol))
+                .collect()
+        })
+        .collect();
+    let total: usize = lines.iter().map(Vec::len).sum();
+
+    // jigsaw puzzles hold as many region symbols as cells, in the second half of every line
+    let (cells, regions): (Vec<&[Located]>, Vec<_>) =
+        if total % 2 == 0 && board_shape(total / 2).is_some() {
+            lines
+                .iter()
+                .map(|line| line.split_at(line.len() / 2))
+                .unzip()
+        } else {
+            (lines.iter().map(Vec::as_slice).collect(), vec![])
+        };
+    let (cells, regions) = (cells.concat(), regions.concat());
+
+    let Some(&(first_line, _, _)) = cells.first() else {
+        unreachable!("blocks are never empty");
+    };
+    let Some(shape) = board_shape(cells.len()) else {
+        let &(line, column, _) = cells.last().unwrap();
+        let sizes = (1..=MAX_SIZE)
+            .filter_map(Shape::for_size)
+            .map(|shape| shape.size())
+            .join(", ");
+        return Err(ParseError {
+            line,
+            column: column + 1,
+            message: format!(
+                "found {} cells, a board is a square of {sizes} cells across",
+                cells.len()
+            ),
+        });
+    };
+
+    let size = shape.size();
+    let shape = if regions.is_empty() {
+        shape
+    } else {
+        shape.with_regions(parse_regions(size, &regions)?)
+    };
+
+    let mut givens = vec![];
+    for (index, &(line, column, symbol)) in cells.iter().enumerate() {
+        let number = match symbol {
+            '.' | '0' => continue,
+            symbol => symbol
+                .to_digit(36)
+                .filter(|&digit| digit <= size)
+                .ok_or_else(|| ParseError {
+                    line,
+                    column,
+                    message: format!("invalid symbol `{symbol}` for a {size}x{size} board"),
+                })?,
+        };
+        let index = index as u32;
+        givens.push(Choice::new(&shape, index / size, index % size, number - 1));
+    }
+
+    Ok(ParsedPuzzle {
+        line: first_line,
+        shape,
+        givens,
+    })
+}
+
+// regions are written like numbers, each of them has as many cells as a row and is one piece
+// of cells that touch at their sides, the board draws borders around them
+fn parse_regions(size: u32, symbols: &[Located]) -> Result<Vec<u32>, ParseError> {
+    let mut counts = vec![0; size as usize];
+    let regions = symbols
+        .iter()
+        .map(|&(line, column, symbol)| {
+            let region = symbol
+                .to_digit(36)
+                .filter(|digit| (1..=size).contains(digit))
+                .ok_or_else(|| ParseError {
+                    line,
+                    column,
+                    message: format!("invalid region `{symbol}` for a {size}x{size} board"),
+                })?
+                - 1;
+
+            counts[region as usize] += 1;
+            if counts[region as usize] > size {
+                return Err(ParseError {
+                    line,
+                    column,
+                    message: format!("region `{symbol}` has more than {size} cells"),
+                });
+            }
+            Ok(region)
+        })
+        .collect::<Result<Vec<u32>, ParseError>>()?;
+
+    match disconnected_cell(size as usize, &regions) {
+        Some(cell) => {
+            let (line, column, symbol) = symbols[cell];
+            Err(ParseError {
+                line,
+                column,
+                message: format!("region `{symbol}` is split into pieces that do not touch"),
+            })
+        }
+        None => Ok(regions),
+    }
+}
+
+// the first cell that does not touch the first cell of its region through cells of the region
+fn disconnected_cell(size: usize, regions: &[u32]) -> Option<usize> {
+    let mut reached = vec![false; regions.len()];
+    let mut seen_regions = vec![false; size];
+    for start in 0..regions.len() {
+        if reached[start] {
+            continue;
+        }
+        let region = regions[start];
+        if seen_regions[region as usize] {
+            return Some(start);
+        }
+        seen_regions[region as usize] = true;
+
+        reached[start] = true;
+        let mut queue = VecDeque::from([start]);
+        while let Some(cell) = queue.pop_front() {
+            let (row, column) = (cell / size, cell % size);
+            let neighbours = [
+                (row > 0).then(|| cell - size),
+                (row + 1 < size).then(|| cell + size),
+                (column > 0).then(|| cell - 1),
+                (column + 1 < size).then(|| cell + 1),
+            ];
+            for other in neighbours.into_iter().flatten() {
+                if regions[other] == region && !reached[other] {
+                    reached[other] = true;
+                    queue.push_back(other);
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::Generator;
+
+    // a puzzle of the shape with a few cells emptied, the givens in the order they are written
+    fn puzzle(shape: Shape, jigsaw: bool) -> (Shape, Vec<Choice>) {
+        let puzzle = Generator {
+            max_removals: Some(20),
+            jigsaw,
+            ..Generator::new(shape, 1)
+        }
+        .generate()
+        .unwrap();
+        (puzzle.shape, puzzle.givens)
+    }
+
+    fn error(format: Format, text: &str) -> (usize, usize, String) {
+        let error = format.parse(text).unwrap_err();
+        (error.line, error.column, error.message)
+    }
+
+    #[test]
+    fn every_format_reads_what_it_writes() {
+        let puzzles = [
+            puzzle(Shape::square(3), false),
+            puzzle(Shape::for_size(6).unwrap(), false),
+            // numbers above 9 are written as letters
+            puzzle(Shape::square(4), false),
+            puzzle(Shape::square(5), false),
+            // regions follow the cells of every line
+            puzzle(Shape::square(3), true),
+        ];
+        for format in Format::ALL {
+            for (shape, givens) in puzzles.iter() {
+                let text = format.write(shape, givens);
+                let parsed = format.parse(&text).unwrap();
+                assert_eq!(parsed.len(), 1, "{}:\n{text}", format.name());
+                assert_eq!(&parsed[0].shape, shape, "{}:\n{text}", format.name());
+                assert_eq!(&parsed[0].givens, givens, "{}:\n{text}", format.name());
+            }
+        }
+    }
+
+    #[test]
+    fn letters_stand_for_numbers_above_nine() {
+        let (shape, givens) = puzzle(Shape::square(5), false);
+        let text = Format::Line.write(&shape, &givens);
+        assert!(text.contains('P'));
+        assert!(!text.contains('Q'));
+        assert_eq!(symbol(9), 'A');
+        assert_eq!(symbol(24), 'P');
+    }
+
+    #[test]
+    fn detects_grids_and_lines() {
+        for (shape, givens) in [
+            puzzle(Shape::square(3), false),
+            puzzle(Shape::square(3), true),
+        ] {
+            for (format, detected) in [
+                (Format::Line, Format::Line),
+                (Format::Sdm, Format::Line),
+                (Format::Grid, Format::Grid),
+                (Format::Sdk, Format::Grid),
+            ] {
+                let text = format.write(&shape, &givens);
+                assert_eq!(Format::detect(&text), detected, "{}", format.name());
+            }
+        }
+    }
+
+    #[test]
+    fn reads_a_puzzle_per_line() {
+        let text = "# a comment\n\
+            005040000002900010307001002000134000030000070000657000100800306080003700000060200\n\
+            \n\
+            010806000700903060050070100009008056000000000240500300001080090070401002000605040\n";
+        let parsed = Format::Sdm.parse(text).unwrap();
+        assert_eq!(
+            parsed.iter().map(|puzzle| puzzle.line).collect::<Vec<_>>(),
+            vec![2, 4]
+        );
+        assert_eq!(parsed[0].givens[0], Choice::new(&Shape::square(3), 0, 2, 4));
+    }
+
+    #[test]
+    fn reads_the_first_section_of_an_sdk_file() {
+        let grid = "..3|...|...\n\
+            ...|...|...\n\
+            ...|...|...\n\
+            ---+---+---\n\
+            ...|...|...\n\
+            ...|...|...\n\
+            ...|...|...\n\
+            ---+---+---\n\
+            ...|...|...\n\
+            ...|...|...\n\
+            ...|...|..9\n";
+        let text = format!("#Aauthor\n{grid}[State]\n{grid}");
+        let parsed = Format::Sdk.parse(&text).unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].line, 2);
+        let shape = Shape::square(3);
+        assert_eq!(
+            parsed[0].givens,
+            vec![Choice::new(&shape, 0, 2, 2), Choice::new(&shape, 8, 8, 8)]
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_symbol() {
+        let mut line = ".".repeat(81);
+        line.replace_range(40..41, "X");
+        assert_eq!(
+            error(Format::Line, &line),
+            (1, 41, "invalid symbol `X` for a 9x9 board".to_string())
+        );
+
+        // the line of the second puzzle, after a comment
+        let text = format!("# puzzles\n{}\n{}A", ".".repeat(81), ".".repeat(80));
+        assert_eq!(
+            error(Format::Sdm, &text),
+            (3, 81, "invalid symbol `A` for a 9x9 board".to_string())
+        );
+
+        let mut grid = vec![".".repeat(9); 9];
+        grid[8] = "........X".to_string();
+        assert_eq!(
+            error(Format::Grid, &grid.join("\n")),
+            (9, 9, "invalid symbol `X` for a 9x9 board".to_string())
+        );
+
+        // separators count towards the column but are not cells
+        let text = "..3|...|...\n".repeat(8) + "...|...|..Z";
+        assert_eq!(
+            error(Format::Grid, &text),
+            (9, 11, "invalid symbol `Z` for a 9x9 board".to_string())
+        );
+
+        // G is 16, the largest number of the board
+        let line = format!("{}GH", ".".repeat(254));
+        assert_eq!(
+            error(Format::Line, &line),
+            (1, 256, "invalid symbol `H` for a 16x16 board".to_string())
+        );
+    }
+
+    #[test]
+    fn errors_on_boards_of_no_size() {
+        let (line, column, message) = error(Format::Line, &".".repeat(80));
+        assert_eq!((line, column), (1, 81));
+        assert!(message.starts_with("found 80 cells"), "{message}");
+    }
+
+    // an empty jigsaw grid with the regions of the usual boxes
+    fn box_regions() -> Vec<String> {
+        (0..9)
+            .map(|row| {
+                let regions: String = (0..9)
+                    .map(|column| symbol(Shape::square(3).square_of(row, column)))
+                    .collect();
+                format!("......... {regions}")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reads_the_regions_after_the_cells() {
+        let parsed = Format::Grid.parse(&box_regions().join("\n")).unwrap();
+        let shape = Shape::square(3);
+        let regions = (0..81).map(|cell| shape.square_of(cell / 9, cell % 9));
+        assert_eq!(parsed[0].shape, shape.with_regions(regions.collect()));
+    }
+
+    #[test]
+    fn errors_on_invalid_regions() {
+        // the regions start after the 9 cells and a space
+        let mut grid = box_regions();
+        grid[8].replace_range(18..19, "0");
+        assert_eq!(
+            error(Format::Grid, &grid.join("\n")),
+            (9, 19, "invalid region `0` for a 9x9 board".to_string())
+        );
+
+        // the first region takes a cell of the second one, and is one cell too big once its
+        // third row is read
+        let mut grid = box_regions();
+        grid[0].replace_range(13..14, "1");
+        assert_eq!(
+            error(Format::Grid, &grid.join("\n")),
+            (3, 13, "region `1` has more than 9 cells".to_string())
+        );
+    }
+
+    #[test]
+    fn errors_on_regions_in_pieces() {
+        // the top left cells of the first two boxes trade places, the first cell of the second
+        // region is cut off from the rest of it, which starts at the fifth cell
+        let mut grid = box_regions();
+        grid[0].replace_range(10..11, "2");
+        grid[0].replace_range(13..14, "1");
+        assert_eq!(
+            error(Format::Grid, &grid.join("\n")),
+            (
+                1,
+                15,
+                "region `2` is split into pieces that do not touch".to_string()
+            )
+        );
+
+        // trading cells along the border of the boxes keeps both regions in one piece
+        let mut grid = box_regions();
+        grid[0].replace_range(12..13, "2");
+        grid[1].replace_range(13..14, "1");
+        assert!(Format::Grid.parse(&grid.join("\n")).is_ok());
+    }
+}
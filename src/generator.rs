@@ -0,0 +1,389 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::exact_cover::{SearchLimit, SolvingState};
+use crate::logic::{self, Grade, Grid};
+use crate::sudoku::{rng_from_seed, Choice, Shape, Solution, Sudoku, SudokuMatrix};
+
+// fresh boards tried when aiming for a grade before settling for the closest one
+const MAX_GRADE_ATTEMPTS: usize = 50;
+
+// search steps per cell of the board before the fill starts over with a fresh order, the
+// attempts get multiples of this following the Luby sequence
+const RESTART_STEPS_PER_CELL: u64 = 20;
+
+// cells traded between regions per cell of the board when drawing a jigsaw layout
+const JIGSAW_TRADES_PER_CELL: u32 = 50;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Symmetry {
+    #[default]
+    None,
+    // cells are removed together with their 180° rotation around the center
+    Rotational,
+    // cells are removed together with their reflection across the main diagonal
+    Diagonal,
+    // cells are removed together with their reflection across the vertical center line
+    Mirror,
+    // cells are removed together with their 90°, 180° and 270° rotations
+    Quarter,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GenerateError {
+    // no board of the shape follows every rule of its variants
+    NoBoard,
+    // filling the board ran out of the time budget or the fill steps
+    GaveUp,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Puzzle {
+    pub shape: Shape,
+    pub givens: Vec<Choice>,
+    pub solution: Solution,
+}
+
+#[derive(Clone, Debug)]
+pub struct Generator {
+    pub shape: Shape,
+    pub seed: u64,
+    // stop removing clues once at most this many are left
+    pub target_clues: Option<usize>,
+    pub symmetry: Symmetry,
+    // give up filling the board and stop removing clues once generating took this long,
+    // a puzzle that comes out is still unique
+    pub time_budget: Option<Duration>,
+    // give up filling the board once the search visited this many partial boards,
+    // unlike the time budget this gives the same result on every machine
+    pub fill_steps: Option<u64>,
+    // stop after trying to remove this many cells or orbits, the same on every machine too
+    pub max_removals: Option<usize>,
+    // only keep removals that leave the puzzle solvable with techniques up to this grade,
+    // and retry with fresh boards until the hardest technique needed is of this grade
+    pub target_grade: Option<Grade>,
+    // replace the boxes with irregular regions drawn from the seed
+    pub jigsaw: bool,
+}
+
+impl Generator {
+    pub fn new(shape: Shape, seed: u64) -> Self {
+        Self {
+            shape,
+            seed,
+            target_clues: None,
+            symmetry: Symmetry::default(),
+            time_budget: None,
+            fill_steps: None,
+            max_removals: None,
+            target_grade: None,
+            jigsaw: false,
+        }
+    }
+
+    pub fn generate(&self) -> Result<Puzzle, GenerateError> {
+        let start = Instant::now();
+        let mut rng = rng_from_seed(self.seed);
+
+        let Some(target) = self.target_grade else {
+            return self.remove_clues(&mut rng, start);
+        };
+
+        let mut closest: Option<(Grade, Puzzle)> = None;
+        for _ in 0..MAX_GRADE_ATTEMPTS {
+            let puzzle = match self.remove_clues(&mut rng, start) {
+                Ok(puzzle) => puzzle,
+                Err(error) => return closest.map(|(_, puzzle)| puzzle).ok_or(error),
+            };
+            // removals are capped at the target, so a harder grade cannot come out
+            let grade = logic::rate(&puzzle.shape, puzzle.givens.iter().copied())
+                .grade()
+                .unwrap_or(Grade::Expert);
+
+            if grade == target {
+                return Ok(puzzle);
+            }
+            if closest
+                .as_ref()
+                .map_or(true, |(closest, _)| grade > *closest)
+            {
+                closest = Some((grade, puzzle));
+            }
+            if self
+                .time_budget
+                .is_some_and(|budget| start.elapsed() >= budget)
+            {
+                break;
+            }
+        }
+        Ok(closest.expect("at least one puzzle was generated").1)
+    }
+
+    // a random filled board. a search that takes long got stuck on its first choices, so it
+    // restarts with a fresh random order, some attempts still get the steps to search every board
+    fn fill(&self, rng: &mut impl Rng, start: Instant) -> Result<Solution, GenerateError> {
+        let deadline = self.time_budget.map(|budget| start + budget);
+        let mut steps_left = self.fill_steps;
+        for attempt in 1.. {
+            let steps = luby(attempt) * RESTART_STEPS_PER_CELL * u64::from(self.shape.cells());
+            let steps = steps_left.map_or(steps, |left| left.min(steps));
+            let mut solution: Option<Solution> = None;
+            let limit = SearchLimit {
+                deadline,
+                steps: Some(steps),
+            };
+            let finished =
+                Sudoku::with_rng(&self.shape, vec![], rng).solve_within(limit, |filled_board| {
+                    solution = Some(filled_board);
+                    SolvingState::Abort
+                });
+            if let Some(solution) = solution {
+                return Ok(solution);
+            }
+            if finished {
+                return Err(GenerateError::NoBoard);
+            }
+
+            steps_left = steps_left.map(|left| left - steps);
+            if steps_left == Some(0) || deadline.is_some_and(|deadline| Instant::now() >= deadline)
+            {
+                return Err(GenerateError::GaveUp);
+            }
+        }
+        unreachable!("the attempts never run out")
+    }
+
+    fn remove_clues(&self, rng: &mut impl Rng, start: Instant) -> Result<Puzzle, GenerateError> {
+        let mut solution = self.fill(rng, start)?;
+
+        // the regions are reshaped around the filled board, searching a fill for a random
+        // layout can take very long and some layouts have none at all
+        let shape = if self.jigsaw {
+            let shape = random_regions(&self.shape, &solution, rng);
+            solution = solution
+                .iter()
+                .map(|choice| Choice::new(&shape, choice.row, choice.column, choice.number))
+                .collect();
+            shape
+        } else {
+            self.shape.clone()
+        };
+        let size = shape.size();
+        // built once, every check below only selects the rows of the givens left
+        let mut matrix = SudokuMatrix::with_rng(&shape, rng);
+
+        // filled values indexed by cell, a cell is emptied by taking its value out
+        let mut board: Vec<Option<Choice>> = vec![None; size.pow(2) as usize];
+        for choice in solution.iter() {
+            board[(choice.row * size + choice.column) as usize] = Some(*choice);
+        }
+
+        // visit cells in a random order so clues are not removed row by row
+        let mut cells: Vec<(u32, u32)> = solution
+            .iter()
+            .map(|choice| (choice.row, choice.column))
+            .collect();
+        cells.shuffle(rng);
+
+        let mut removals = 0;
+        for (row, column) in cells {
+            if self
+                .target_clues
+                .is_some_and(|target| board.iter().flatten().count() <= target)
+                || self
+                    .time_budget
+                    .is_some_and(|budget| start.elapsed() >= budget)
+                || self.max_removals.is_some_and(|max| removals >= max)
+            {
+                break;
+            }
+
+            let removed: Vec<Choice> = self
+                .symmetry
+                .orbit(size, row, column)
+                .into_iter()
+                .filter_map(|(row, column)| board[(row * size + column) as usize].take())
+                .collect();
+
+            if removed.is_empty() {
+                continue;
+            }
+            removals += 1;
+
+            let givens: Vec<Choice> = board.iter().flatten().copied().collect();
+            let keep = match self.target_grade {
+                // solving with logic alone also proves the solution is unique
+                Some(target) => Grid::new(&shape, givens).solve(target).solved,
+                None => matrix.is_unique(&givens),
+            };
+            if !keep {
+                for choice in removed {
+                    board[(choice.row * size + choice.column) as usize] = Some(choice);
+                }
+            }
+        }
+
+        Ok(Puzzle {
+            shape,
+            givens: board.into_iter().flatten().collect(),
+            solution,
+        })
+    }
+}
+
+// 1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8, ... for the attempts counted from 1,
+// restarting after these multiples wastes little more than the best fixed number of steps would
+fn luby(mut attempt: u64) -> u64 {
+    loop {
+        let bits = u64::BITS - attempt.leading_zeros();
+        if attempt == (1 << bits) - 1 {
+            return 1 << (bits - 1);
+        }
+        attempt -= (1 << (bits - 1)) - 1;
+    }
+}
+
+impl fmt::Display for GenerateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenerateError::NoBoard => write!(f, "no board follows the rules of every variant"),
+            GenerateError::GaveUp => {
+                write!(f, "gave up filling the board")
+            }
+        }
+    }
+}
+
+impl std::error::Error for GenerateError {}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 5] = [
+        Symmetry::None,
+        Symmetry::Rotational,
+        Symmetry::Diagonal,
+        Symmetry::Mirror,
+        Symmetry::Quarter,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Symmetry::None => "none",
+            Symmetry::Rotational => "rotational",
+            Symmetry::Diagonal => "diagonal",
+            Symmetry::Mirror => "mirror",
+            Symmetry::Quarter => "quarter",
+        }
+    }
+
+    // maps a cell to its image, applying it repeatedly walks the whole orbit of the cell
+    fn transform(&self, size: u32, (row, column): (u32, u32)) -> (u32, u32) {
+        let last = size - 1;
+        match self {
+            Symmetry::None => (row, column),
+            Symmetry::Rotational => (last - row, last - column),
+            Symmetry::Diagonal => (column, row),
+            Symmetry::Mirror => (row, last - column),
+            Symmetry::Quarter => (column, last - row),
+        }
+    }
+
+    // cells that have to be removed together with the cell at row and column
+    pub fn orbit(&self, size: u32, row: u32, column: u32) -> Vec<(u32, u32)> {
+        let mut cells = vec![(row, column)];
+        loop {
+            let next = self.transform(size, *cells.last().unwrap());
+            if next == cells[0] {
+                return cells;
+            }
+            cells.push(next);
+        }
+    }
+}
+
+// starts from the regions of the shape and keeps trading a cell on the edge of a region for
+// the cell of its neighbour that holds the same number in the filled board, so every region
+// keeps its size, stays in one piece and still holds every number once
+fn random_regions(shape: &Shape, solution: &Solution, rng: &mut impl Rng) -> Shape {
+    let size = shape.size();
+    let mut numbers = vec![0; size.pow(2) as usize];
+    for choice in solution {
+        numbers[(choice.row * size + choice.column) as usize] = choice.number;
+    }
+
+    let mut regions: Vec<u32> = (0..size.pow(2))
+        .map(|cell| shape.square_of(cell / size, cell % size))
+        .collect();
+
+    let neighbours = |cell: u32| {
+        let (row, column) = (cell / size, cell % size);
+        [
+            (row > 0).then(|| cell - size),
+            (row + 1 < size).then(|| cell + size),
+            (column > 0).then(|| cell - 1),
+            (column + 1 < size).then(|| cell + 1),
+        ]
+        .into_iter()
+        .flatten()
+    };
+    let touches = |regions: &[u32], cell: u32, region: u32| {
+        neighbours(cell).any(|other| regions[other as usize] == region)
+    };
+    let is_connected = |regions: &[u32], region: u32| {
+        let cells: Vec<u32> = (0..size.pow(2))
+            .filter(|&cell| regions[cell as usize] == region)
+            .collect();
+        let mut seen = vec![false; regions.len()];
+        let mut queue = VecDeque::from([cells[0]]);
+        seen[cells[0] as usize] = true;
+        let mut reached = 0;
+        while let Some(cell) = queue.pop_front() {
+            reached += 1;
+            for other in neighbours(cell) {
+                if regions[other as usize] == region && !seen[other as usize] {
+                    seen[other as usize] = true;
+                    queue.push_back(other);
+                }
+            }
+        }
+        reached == cells.len()
+    };
+
+    for _ in 0..size.pow(2) * JIGSAW_TRADES_PER_CELL {
+        // a cell of one region moves into a neighbouring region,
+        // which gives back its cell with the same number if that touches the first region
+        let cell = rng.gen_range(0..size.pow(2));
+        let from = regions[cell as usize];
+        let Some(&to) = neighbours(cell)
+            .map(|other| regions[other as usize])
+            .filter(|&region| region != from)
+            .collect::<Vec<u32>>()
+            .choose(rng)
+        else {
+            continue;
+        };
+        regions[cell as usize] = to;
+
+        let Some(other) = (0..size.pow(2)).find(|&other| {
+            other != cell
+                && regions[other as usize] == to
+                && numbers[other as usize] == numbers[cell as usize]
+        }) else {
+            unreachable!("every region holds every number");
+        };
+        if !touches(&regions, other, from) {
+            regions[cell as usize] = from;
+            continue;
+        }
+        regions[other as usize] = from;
+
+        if !(is_connected(&regions, from) && is_connected(&regions, to)) {
+            regions[cell as usize] = from;
+            regions[other as usize] = to;
+        }
+    }
+    shape.with_regions(regions)
+}
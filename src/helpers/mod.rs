@@ -77,7 +77,7 @@ pub fn debug_startup(mut commands: Commands) {
     ));
 }
 
-pub fn validate_heap<K: PartialOrd + Debug, V: Debug>(tree: &Vec<Option<(K, V)>>, asc: bool) {
+pub fn validate_heap<K: PartialOrd + Debug, V: Debug>(tree: &[Option<(K, V)>], asc: bool) {
     for i in 1..tree.len() {
         if let Some((k1, _)) = &tree[i] {
             let left = i * 2;
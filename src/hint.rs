@@ -0,0 +1,104 @@
+use bevy::prelude::*;
+use itertools::Itertools;
+use sudoku::{format::symbol, Grade, Grid, Step};
+
+use crate::board::Board;
+use crate::input::uses_letters;
+
+pub const INVOLVED_COLOR: Color = Color::rgb(1., 0.9, 0.5);
+pub const PLACEMENT_COLOR: Color = Color::rgb(0.6, 1., 0.6);
+pub const ELIMINATION_COLOR: Color = Color::rgb(1., 0.6, 0.6);
+
+#[derive(Component)]
+pub struct HintText;
+
+// the next logical step for the grid on screen, if the player asked for one
+#[derive(Resource, Default)]
+pub struct Hint(pub Option<Step>);
+
+pub fn request_hint(
+    input_keyboard: Res<ButtonInput<KeyCode>>,
+    board: Res<Board>,
+    mut hint: ResMut<Hint>,
+    mut query: Query<&mut Text, With<HintText>>,
+) {
+    let Ok(mut text) = query.get_single_mut() else {
+        return;
+    };
+
+    if input_keyboard.just_pressed(KeyCode::Escape) {
+        hint.0 = None;
+        text.sections[0].value.clear();
+    }
+    // H is a number on boards played with letters, F1 always works
+    let requested = input_keyboard.just_pressed(KeyCode::F1)
+        || (!uses_letters(&board) && input_keyboard.just_pressed(KeyCode::KeyH));
+    if !requested {
+        return;
+    }
+
+    // hints are derived from what the player entered, not from the stored solution
+    let choices: Vec<_> = board.choices().collect();
+    let has_conflicts = !sudoku::conflicting(&board.shape, &choices).is_empty();
+
+    hint.0 = if has_conflicts {
+        None
+    } else {
+        Grid::new(&board.shape, choices).next_step(Grade::Expert)
+    };
+
+    text.sections[0].value = match &hint.0 {
+        Some(step) => describe(step),
+        None if has_conflicts => "No hint: some numbers break a row, column or box rule".into(),
+        None => "No hint: no technique applies to this grid".into(),
+    };
+}
+
+fn describe(step: &Step) -> String {
+    let cell = |row: u32, column: u32| format!("r{}c{}", row + 1, column + 1);
+
+    let action = match step.placement {
+        Some(choice) => format!(
+            "Place {} in {}",
+            symbol(choice.number),
+            cell(choice.row, choice.column)
+        ),
+        None => {
+            let eliminations = step
+                .eliminations
+                .iter()
+                .into_group_map_by(|choice| choice.number)
+                .into_iter()
+                .sorted_by_key(|(number, _)| *number)
+                .map(|(number, choices)| {
+                    format!(
+                        "{} from {}",
+                        symbol(number),
+                        choices
+                            .iter()
+                            .map(|choice| cell(choice.row, choice.column))
+                            .join(", ")
+                    )
+                })
+                .join("; ");
+            format!("Remove {eliminations}")
+        }
+    };
+    format!("{step}\n{action}")
+}
+
+// a hint is stale as soon as the grid changes
+pub fn clear_hint(
+    board: Res<Board>,
+    mut hint: ResMut<Hint>,
+    mut query: Query<&mut Text, With<HintText>>,
+) {
+    if !board.is_changed() || board.is_added() || hint.0.is_none() {
+        return;
+    }
+
+    hint.0 = None;
+    if let Ok(mut text) = query.get_single_mut() {
+        text.sections[0].value.clear();
+    }
+}
@@ -0,0 +1,123 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use sudoku::Choice;
+
+use crate::board::Board;
+
+// a change the player made to the board, holds enough to take it back again
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Command {
+    Place {
+        choice: Choice,
+        // the entry that was in the cell before
+        replaced: Option<Choice>,
+        // placing removes the number from the notes of peers, these are the cells it was
+        // removed from, indexed by row * size + column
+        cleared_notes: Vec<u32>,
+    },
+    Erase {
+        choice: Choice,
+    },
+    ToggleNote {
+        row: u32,
+        column: u32,
+        number: u32,
+    },
+    FillNotes {
+        before: Vec<u32>,
+        after: Vec<u32>,
+    },
+}
+
+impl Command {
+    pub fn apply(&self, board: &mut Board) {
+        match self {
+            Command::Place { choice, .. } => board.place(*choice),
+            Command::Erase { choice } => board.clear(choice.row, choice.column),
+            Command::ToggleNote {
+                row,
+                column,
+                number,
+            } => board.toggle_note(*row, *column, *number),
+            Command::FillNotes { after, .. } => board.notes.clone_from(after),
+        }
+    }
+
+    pub fn revert(&self, board: &mut Board) {
+        match self {
+            Command::Place {
+                choice,
+                replaced,
+                cleared_notes,
+            } => {
+                board.clear(choice.row, choice.column);
+                board.entries.extend(replaced);
+                for &cell in cleared_notes {
+                    board.notes[cell as usize] |= 1 << choice.number;
+                }
+            }
+            Command::Erase { choice } => board.entries.push(*choice),
+            Command::ToggleNote {
+                row,
+                column,
+                number,
+            } => board.toggle_note(*row, *column, *number),
+            Command::FillNotes { before, .. } => board.notes.clone_from(before),
+        }
+    }
+}
+
+// commands in the order they were made, undone ones are kept until a new command is made
+#[derive(Resource, Clone, Default, Serialize, Deserialize)]
+pub struct History {
+    pub done: Vec<Command>,
+    pub undone: Vec<Command>,
+}
+
+impl History {
+    pub fn execute(&mut self, command: Command, board: &mut Board) {
+        command.apply(board);
+        self.done.push(command);
+        self.undone.clear();
+    }
+
+    pub fn undo(&mut self, board: &mut Board) {
+        if let Some(command) = self.done.pop() {
+            command.revert(board);
+            self.undone.push(command);
+        }
+    }
+
+    pub fn redo(&mut self, board: &mut Board) {
+        if let Some(command) = self.undone.pop() {
+            command.apply(board);
+            self.done.push(command);
+        }
+    }
+}
+
+pub fn is_control_pressed(input_keyboard: &ButtonInput<KeyCode>) -> bool {
+    input_keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+}
+
+// Ctrl+Z undoes the last command, Ctrl+Y or Ctrl+Shift+Z redoes it
+pub fn undo_redo(
+    input_keyboard: Res<ButtonInput<KeyCode>>,
+    mut history: ResMut<History>,
+    mut board: ResMut<Board>,
+) {
+    if !is_control_pressed(&input_keyboard) {
+        return;
+    }
+    let shift = input_keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    if input_keyboard.just_pressed(KeyCode::KeyY)
+        || (shift && input_keyboard.just_pressed(KeyCode::KeyZ))
+    {
+        if !history.undone.is_empty() {
+            history.redo(&mut board);
+        }
+    } else if input_keyboard.just_pressed(KeyCode::KeyZ) && !history.done.is_empty() {
+        history.undo(&mut board);
+    }
+}
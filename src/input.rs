@@ -0,0 +1,206 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_ecs_tilemap::{
+    map::{TilemapGridSize, TilemapSize, TilemapType},
+    tiles::TilePos,
+};
+use sudoku::Choice;
+
+use crate::board::{cell_at, Board, Selection, SudokuBoardFG};
+use crate::history::{is_control_pressed, Command, History};
+use crate::notes::NotesMode;
+
+const DIGIT_KEYS: [(KeyCode, KeyCode); 9] = [
+    (KeyCode::Digit1, KeyCode::Numpad1),
+    (KeyCode::Digit2, KeyCode::Numpad2),
+    (KeyCode::Digit3, KeyCode::Numpad3),
+    (KeyCode::Digit4, KeyCode::Numpad4),
+    (KeyCode::Digit5, KeyCode::Numpad5),
+    (KeyCode::Digit6, KeyCode::Numpad6),
+    (KeyCode::Digit7, KeyCode::Numpad7),
+    (KeyCode::Digit8, KeyCode::Numpad8),
+    (KeyCode::Digit9, KeyCode::Numpad9),
+];
+
+const LETTER_KEYS: [KeyCode; 26] = [
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+];
+
+const CLEAR_KEYS: [KeyCode; 4] = [
+    KeyCode::Backspace,
+    KeyCode::Delete,
+    KeyCode::Digit0,
+    KeyCode::Numpad0,
+];
+
+// boards with more than 9 numbers are played with letters, which takes WASD away from movement
+pub fn uses_letters(board: &Board) -> bool {
+    board.size() > 9
+}
+
+pub fn select_with_mouse(
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    board_fg_query: Query<
+        (
+            &TilemapSize,
+            &TilemapGridSize,
+            &TilemapType,
+            &GlobalTransform,
+        ),
+        With<SudokuBoardFG>,
+    >,
+    input_mouse: Res<ButtonInput<MouseButton>>,
+    board: Res<Board>,
+    mut selection: ResMut<Selection>,
+) {
+    if !input_mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let (Ok(window), Ok((camera, camera_transform))) =
+        (window_query.get_single(), camera_query.get_single())
+    else {
+        return;
+    };
+    let Some(world_pos) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor))
+    else {
+        return;
+    };
+    let Ok((map_size, grid_size, map_type, map_transform)) = board_fg_query.get_single() else {
+        return;
+    };
+
+    // tile positions are relative to the tilemap, so undo its transform first
+    let local_pos = map_transform
+        .compute_matrix()
+        .inverse()
+        .transform_point3(world_pos.extend(0.))
+        .truncate();
+
+    selection.0 = TilePos::from_world_pos(&local_pos, map_size, grid_size, map_type)
+        .map(|pos| cell_at(board.size(), &pos));
+}
+
+pub fn move_selection(
+    input_keyboard: Res<ButtonInput<KeyCode>>,
+    board: Res<Board>,
+    mut selection: ResMut<Selection>,
+) {
+    let wasd = !uses_letters(&board);
+    let pressed = |arrow: KeyCode, letter: KeyCode| {
+        input_keyboard.just_pressed(arrow) || (wasd && input_keyboard.just_pressed(letter))
+    };
+
+    let mut delta = (0, 0);
+    if pressed(KeyCode::ArrowUp, KeyCode::KeyW) {
+        delta.0 -= 1;
+    }
+    if pressed(KeyCode::ArrowDown, KeyCode::KeyS) {
+        delta.0 += 1;
+    }
+    if pressed(KeyCode::ArrowLeft, KeyCode::KeyA) {
+        delta.1 -= 1;
+    }
+    if pressed(KeyCode::ArrowRight, KeyCode::KeyD) {
+        delta.1 += 1;
+    }
+    if delta == (0, 0) {
+        return;
+    }
+
+    let size = board.size() as i32;
+    let (row, column) = selection.0.unwrap_or((0, 0));
+    selection.0 = Some((
+        (row as i32 + delta.0).clamp(0, size - 1) as u32,
+        (column as i32 + delta.1).clamp(0, size - 1) as u32,
+    ));
+}
+
+// the number the pressed key stands for, if any, numbers above 9 are typed as letters
+fn pressed_number(input_keyboard: &ButtonInput<KeyCode>, board: &Board) -> Option<u32> {
+    let numbers = board.size() as usize;
+    let digit = DIGIT_KEYS
+        .iter()
+        .take(numbers)
+        .position(|&(digit, numpad)| {
+            input_keyboard.just_pressed(digit) || input_keyboard.just_pressed(numpad)
+        });
+    let letter = LETTER_KEYS
+        .iter()
+        .take(numbers.saturating_sub(DIGIT_KEYS.len()))
+        .position(|&key| input_keyboard.just_pressed(key))
+        .map(|letter| DIGIT_KEYS.len() + letter);
+
+    digit.or(letter).map(|number| number as u32)
+}
+
+pub fn enter_number(
+    input_keyboard: Res<ButtonInput<KeyCode>>,
+    mut board: ResMut<Board>,
+    mut history: ResMut<History>,
+    selection: Res<Selection>,
+    notes_mode: Res<NotesMode>,
+) {
+    let Some((row, column)) = selection.0 else {
+        return;
+    };
+    // letters pressed with control are shortcuts, not numbers
+    if board.is_given(row, column) || is_control_pressed(&input_keyboard) {
+        return;
+    }
+
+    let entry = board.get(row, column);
+    let command = if let Some(number) = pressed_number(&input_keyboard, &board) {
+        if notes_mode.0 {
+            // notes are only taken in empty cells
+            entry.is_none().then_some(Command::ToggleNote {
+                row,
+                column,
+                number,
+            })
+        } else if entry.map(|choice| choice.number) != Some(number) {
+            let choice = Choice::new(&board.shape, row, column, number);
+            Some(Command::Place {
+                choice,
+                replaced: entry,
+                cleared_notes: board.notes_cleared_by(choice),
+            })
+        } else {
+            None
+        }
+    } else if input_keyboard.any_just_pressed(CLEAR_KEYS) {
+        entry.map(|choice| Command::Erase { choice })
+    } else {
+        None
+    };
+
+    if let Some(command) = command {
+        history.execute(command, &mut board);
+    }
+}
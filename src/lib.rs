@@ -0,0 +1,19 @@
+pub mod dancing_links;
+pub mod exact_cover;
+pub mod format;
+pub mod generator;
+pub mod logic;
+pub mod problem;
+pub mod sudoku;
+pub mod variant;
+
+pub use exact_cover::{Matrix, MatrixSize, SearchLimit, SolvingState};
+pub use format::{Format, ParseError, ParsedPuzzle};
+pub use generator::{GenerateError, Generator, Puzzle, Symmetry};
+pub use logic::{Grade, Grid, Step, Technique};
+pub use problem::ExactCoverProblem;
+pub use sudoku::{
+    conflicting, is_complete, rng_from_seed, CellHouses, Choice, Shape, Solution, Sudoku,
+    SudokuMatrix,
+};
+pub use variant::Variant;
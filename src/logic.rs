@@ -0,0 +1,1112 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use itertools::Itertools;
+
+use crate::format::symbol;
+use crate::sudoku::{Choice, Shape};
+use crate::variant::Variant;
+
+// candidates of a cell are stored as a bitmask, bit k is set when number k is still possible
+type Candidates = u32;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
+pub enum Grade {
+    Easy,
+    Medium,
+    Hard,
+    Expert,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Technique {
+    NakedSingle,
+    HiddenSingle,
+    NakedPair,
+    HiddenPair,
+    NakedTriple,
+    HiddenTriple,
+    Pointing,
+    Claiming,
+    XWing,
+    Swordfish,
+    XYWing,
+    XChain,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Unit {
+    Row(u32),
+    Column(u32),
+    Box(u32),
+    // an extra house of a variant, like a diagonal or a window
+    House(Variant, u32),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Step {
+    pub technique: Technique,
+    // every cell the deduction looks at, as (row, column)
+    pub cells: Vec<(u32, u32)>,
+    pub placement: Option<Choice>,
+    pub eliminations: Vec<Choice>,
+    pub description: String,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Trace {
+    pub steps: Vec<Step>,
+    pub solved: bool,
+}
+
+// the board as a human sees it, with the remaining candidates of every empty cell
+#[derive(Clone, Debug)]
+pub struct Grid {
+    shape: Shape,
+    size: u32,
+    values: Vec<Option<u32>>,
+    candidates: Vec<Candidates>,
+    units: Vec<(Unit, Vec<usize>)>,
+    // cells that can not hold the same number as the cell, indexed by cell
+    peers: Vec<Vec<usize>>,
+}
+
+impl Grade {
+    pub const ALL: [Grade; 4] = [Grade::Easy, Grade::Medium, Grade::Hard, Grade::Expert];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Grade::Easy => "easy",
+            Grade::Medium => "medium",
+            Grade::Hard => "hard",
+            Grade::Expert => "expert",
+        }
+    }
+}
+
+impl Technique {
+    // techniques in the order a human would try them
+    pub const ALL: [Technique; 12] = [
+        Technique::NakedSingle,
+        Technique::HiddenSingle,
+        Technique::NakedPair,
+        Technique::HiddenPair,
+        Technique::Pointing,
+        Technique::Claiming,
+        Technique::NakedTriple,
+        Technique::HiddenTriple,
+        Technique::XWing,
+        Technique::Swordfish,
+        Technique::XYWing,
+        Technique::XChain,
+    ];
+
+    pub fn grade(&self) -> Grade {
+        match self {
+            Technique::NakedSingle | Technique::HiddenSingle => Grade::Easy,
+            Technique::NakedPair
+            | Technique::HiddenPair
+            | Technique::NakedTriple
+            | Technique::HiddenTriple
+            | Technique::Pointing
+            | Technique::Claiming => Grade::Medium,
+            Technique::XWing | Technique::Swordfish | Technique::XYWing => Grade::Hard,
+            Technique::XChain => Grade::Expert,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Technique::NakedSingle => "Naked single",
+            Technique::HiddenSingle => "Hidden single",
+            Technique::NakedPair => "Naked pair",
+            Technique::HiddenPair => "Hidden pair",
+            Technique::NakedTriple => "Naked triple",
+            Technique::HiddenTriple => "Hidden triple",
+            Technique::Pointing => "Pointing",
+            Technique::Claiming => "Claiming",
+            Technique::XWing => "X-Wing",
+            Technique::Swordfish => "Swordfish",
+            Technique::XYWing => "XY-Wing",
+            Technique::XChain => "X-Chain",
+        }
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unit::Row(row) => write!(f, "row {}", row + 1),
+            Unit::Column(column) => write!(f, "column {}", column + 1),
+            Unit::Box(square) => write!(f, "box {}", square + 1),
+            Unit::House(Variant::Windoku, house) => write!(f, "window {}", house + 1),
+            Unit::House(variant, house) => write!(f, "{} {}", variant.name(), house + 1),
+        }
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.description)
+    }
+}
+
+impl Trace {
+    // the grade of the hardest technique used, None when logic alone could not solve the puzzle
+    pub fn grade(&self) -> Option<Grade> {
+        if !self.solved {
+            return None;
+        }
+        Some(
+            self.steps
+                .iter()
+                .map(|step| step.technique.grade())
+                .max()
+                .unwrap_or(Grade::Easy),
+        )
+    }
+}
+
+fn cell_name((row, column): (u32, u32)) -> String {
+    format!("r{}c{}", row + 1, column + 1)
+}
+
+fn numbers(candidates: Candidates) -> impl Iterator<Item = u32> {
+    (0..Candidates::BITS).filter(move |number| candidates & (1 << number) != 0)
+}
+
+fn list_numbers(candidates: Candidates) -> String {
+    numbers(candidates).map(symbol).join(", ")
+}
+
+pub fn rate(shape: &Shape, givens: impl IntoIterator<Item = Choice>) -> Trace {
+    Grid::new(shape, givens).solve(Grade::Expert)
+}
+
+impl Grid {
+    pub fn new(shape: &Shape, filled_values: impl IntoIterator<Item = Choice>) -> Self {
+        let size = shape.size();
+        let cells = size.pow(2) as usize;
+
+        let mut units: Vec<(Unit, Vec<usize>)> = vec![];
+        for row in 0..size {
+            units.push((
+                Unit::Row(row),
+                (0..size)
+                    .map(|column| (row * size + column) as usize)
+                    .collect(),
+            ));
+        }
+        for column in 0..size {
+            units.push((
+                Unit::Column(column),
+                (0..size)
+                    .map(|row| (row * size + column) as usize)
+                    .collect(),
+            ));
+        }
+        for square in 0..size {
+            units.push((
+                Unit::Box(square),
+                shape
+                    .square_cells(square)
+                    .into_iter()
+                    .map(|(row, column)| (row * size + column) as usize)
+                    .collect(),
+            ));
+        }
+        for &variant in shape.variants.iter() {
+            for (house, house_cells) in variant.houses(shape).into_iter().enumerate() {
+                units.push((
+                    Unit::House(variant, house as u32),
+                    house_cells
+                        .into_iter()
+                        .map(|(row, column)| (row * size + column) as usize)
+                        .collect(),
+                ));
+            }
+        }
+
+        let mut peers: Vec<Vec<usize>> = vec![vec![]; cells];
+        for (_, unit_cells) in units.iter() {
+            for (&a, &b) in unit_cells.iter().tuple_combinations() {
+                peers[a].push(b);
+                peers[b].push(a);
+            }
+        }
+        for &variant in shape.variants.iter() {
+            for (row, column) in (0..size).cartesian_product(0..size) {
+                peers[(row * size + column) as usize].extend(
+                    variant
+                        .forbidden_cells(shape, row, column)
+                        .map(|(row, column)| (row * size + column) as usize),
+                );
+            }
+        }
+        for cell_peers in peers.iter_mut() {
+            cell_peers.sort_unstable();
+            cell_peers.dedup();
+        }
+
+        let mut grid = Self {
+            shape: shape.clone(),
+            size,
+            values: vec![None; cells],
+            candidates: vec![(1 << size) - 1; cells],
+            units,
+            peers,
+        };
+        for choice in filled_values {
+            grid.place(choice);
+        }
+        grid
+    }
+
+    pub fn shape(&self) -> &Shape {
+        &self.shape
+    }
+
+    pub fn value(&self, row: u32, column: u32) -> Option<u32> {
+        self.values[(row * self.size + column) as usize]
+    }
+
+    pub fn candidates(&self, row: u32, column: u32) -> impl Iterator<Item = u32> {
+        numbers(self.candidates[(row * self.size + column) as usize])
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.values.iter().all(Option::is_some)
+    }
+
+    // places a number and removes it from the candidates of every peer
+    pub fn place(&mut self, choice: Choice) {
+        let cell = (choice.row * self.size + choice.column) as usize;
+        self.values[cell] = Some(choice.number);
+        self.candidates[cell] = 0;
+
+        for peer in self.peers(cell).collect::<Vec<usize>>() {
+            self.candidates[peer] &= !(1 << choice.number);
+        }
+    }
+
+    pub fn eliminate(&mut self, choice: Choice) {
+        self.candidates[(choice.row * self.size + choice.column) as usize] &= !(1 << choice.number);
+    }
+
+    pub fn apply(&mut self, step: &Step) {
+        if let Some(choice) = step.placement {
+            self.place(choice);
+        }
+        for choice in step.eliminations.iter() {
+            self.eliminate(*choice);
+        }
+    }
+
+    // applies steps until the grid is solved or no technique up to the given grade makes progress
+    pub fn solve(&mut self, max_grade: Grade) -> Trace {
+        let mut trace = Trace::default();
+        while !self.is_solved() {
+            match self.next_step(max_grade) {
+                Some(step) => {
+                    self.apply(&step);
+                    trace.steps.push(step);
+                }
+                None => break,
+            }
+        }
+        trace.solved = self.is_solved();
+        trace
+    }
+
+    pub fn next_step(&self, max_grade: Grade) -> Option<Step> {
+        // a cell without candidates means the grid is broken and any deduction would be unsound
+        if (0..self.values.len())
+            .any(|cell| self.values[cell].is_none() && self.candidates[cell] == 0)
+        {
+            return None;
+        }
+
+        Technique::ALL
+            .into_iter()
+            .filter(|technique| technique.grade() <= max_grade)
+            .find_map(|technique| match technique {
+                Technique::NakedSingle => self.naked_single(),
+                Technique::HiddenSingle => self.hidden_single(),
+                Technique::NakedPair => self.naked_subset(2),
+                Technique::HiddenPair => self.hidden_subset(2),
+                Technique::NakedTriple => self.naked_subset(3),
+                Technique::HiddenTriple => self.hidden_subset(3),
+                Technique::Pointing => self.pointing(),
+                Technique::Claiming => self.claiming(),
+                Technique::XWing => self.fish(2),
+                Technique::Swordfish => self.fish(3),
+                Technique::XYWing => self.xy_wing(),
+                Technique::XChain => self.x_chain(),
+            })
+    }
+
+    fn position(&self, cell: usize) -> (u32, u32) {
+        (cell as u32 / self.size, cell as u32 % self.size)
+    }
+
+    fn choice(&self, cell: usize, number: u32) -> Choice {
+        let (row, column) = self.position(cell);
+        Choice::new(&self.shape, row, column, number)
+    }
+
+    fn sees(&self, a: usize, b: usize) -> bool {
+        self.peers[a].binary_search(&b).is_ok()
+    }
+
+    fn peers(&self, cell: usize) -> impl Iterator<Item = usize> + '_ {
+        self.peers[cell].iter().copied()
+    }
+
+    fn empty_cells<'a>(&'a self, cells: &'a [usize]) -> impl Iterator<Item = usize> + 'a {
+        cells
+            .iter()
+            .copied()
+            .filter(|&cell| self.values[cell].is_none())
+    }
+
+    fn has_candidate(&self, cell: usize, number: u32) -> bool {
+        self.candidates[cell] & (1 << number) != 0
+    }
+
+    // builds a step out of the eliminations, or nothing when there is nothing to eliminate
+    fn elimination_step(
+        &self,
+        technique: Technique,
+        cells: impl IntoIterator<Item = usize>,
+        eliminations: impl IntoIterator<Item = (usize, u32)>,
+        description: String,
+    ) -> Option<Step> {
+        let eliminations: Vec<Choice> = eliminations
+            .into_iter()
+            .filter(|&(cell, number)| self.has_candidate(cell, number))
+            .unique()
+            .map(|(cell, number)| self.choice(cell, number))
+            .collect();
+
+        if eliminations.is_empty() {
+            return None;
+        }
+        Some(Step {
+            technique,
+            cells: cells.into_iter().map(|cell| self.position(cell)).collect(),
+            placement: None,
+            eliminations,
+            description,
+        })
+    }
+
+    fn naked_single(&self) -> Option<Step> {
+        (0..self.values.len())
+            .find(|&cell| self.candidates[cell].count_ones() == 1)
+            .map(|cell| {
+                let number = self.candidates[cell].trailing_zeros();
+                Step {
+                    technique: Technique::NakedSingle,
+                    cells: vec![self.position(cell)],
+                    placement: Some(self.choice(cell, number)),
+                    eliminations: vec![],
+                    description: format!(
+                        "Naked single: {} is the only candidate left in {}",
+                        symbol(number),
+                        cell_name(self.position(cell))
+                    ),
+                }
+            })
+    }
+
+    fn hidden_single(&self) -> Option<Step> {
+        self.units.iter().find_map(|(unit, cells)| {
+            (0..self.size).find_map(|number| {
+                let (cell,) = self
+                    .empty_cells(cells)
+                    .filter(|&cell| self.has_candidate(cell, number))
+                    .collect_tuple()?;
+                Some(Step {
+                    technique: Technique::HiddenSingle,
+                    cells: self
+                        .empty_cells(cells)
+                        .map(|cell| self.position(cell))
+                        .collect(),
+                    placement: Some(self.choice(cell, number)),
+                    eliminations: vec![],
+                    description: format!(
+                        "Hidden single in {unit}: {} can only go in {}",
+                        symbol(number),
+                        cell_name(self.position(cell))
+                    ),
+                })
+            })
+        })
+    }
+
+    fn naked_subset(&self, k: usize) -> Option<Step> {
+        let technique = if k == 2 {
+            Technique::NakedPair
+        } else {
+            Technique::NakedTriple
+        };
+
+        self.units.iter().find_map(|(unit, cells)| {
+            let empty: Vec<usize> = self.empty_cells(cells).collect();
+            empty.iter().copied().combinations(k).find_map(|subset| {
+                let union = subset
+                    .iter()
+                    .fold(0, |union, &cell| union | self.candidates[cell]);
+                if union.count_ones() as usize != k {
+                    return None;
+                }
+
+                let eliminations = empty
+                    .iter()
+                    .filter(|cell| !subset.contains(cell))
+                    .flat_map(|&cell| numbers(union).map(move |number| (cell, number)));
+                self.elimination_step(
+                    technique,
+                    subset.clone(),
+                    eliminations,
+                    format!(
+                        "{} {{{}}} in {unit}: {}",
+                        technique.name(),
+                        list_numbers(union),
+                        subset
+                            .iter()
+                            .map(|&cell| cell_name(self.position(cell)))
+                            .join(", ")
+                    ),
+                )
+            })
+        })
+    }
+
+    fn hidden_subset(&self, k: usize) -> Option<Step> {
+        let technique = if k == 2 {
+            Technique::HiddenPair
+        } else {
+            Technique::HiddenTriple
+        };
+
+        self.units.iter().find_map(|(unit, cells)| {
+            let empty: Vec<usize> = self.empty_cells(cells).collect();
+            let open = empty
+                .iter()
+                .fold(0, |union, &cell| union | self.candidates[cell]);
+
+            numbers(open).combinations(k).find_map(|subset| {
+                let mask = subset.iter().fold(0, |mask, number| mask | (1 << number));
+                let positions: Vec<usize> = empty
+                    .iter()
+                    .copied()
+                    .filter(|&cell| self.candidates[cell] & mask != 0)
+                    .collect();
+                if positions.len() != k {
+                    return None;
+                }
+
+                let eliminations = positions.iter().flat_map(|&cell| {
+                    numbers(self.candidates[cell] & !mask).map(move |number| (cell, number))
+                });
+                self.elimination_step(
+                    technique,
+                    positions.clone(),
+                    eliminations,
+                    format!(
+                        "{} {{{}}} in {unit}: {}",
+                        technique.name(),
+                        list_numbers(mask),
+                        positions
+                            .iter()
+                            .map(|&cell| cell_name(self.position(cell)))
+                            .join(", ")
+                    ),
+                )
+            })
+        })
+    }
+
+    // candidates of a number in a box that all lie on one line remove it from the rest of that line
+    fn pointing(&self) -> Option<Step> {
+        let boxes = self
+            .units
+            .iter()
+            .filter(|(unit, _)| matches!(unit, Unit::Box(_)));
+        let lines = self
+            .units
+            .iter()
+            .filter(|(unit, _)| !matches!(unit, Unit::Box(_)));
+        self.intersection(Technique::Pointing, boxes, lines)
+    }
+
+    // candidates of a number in a line that all lie in one box remove it from the rest of that box
+    fn claiming(&self) -> Option<Step> {
+        let lines = self
+            .units
+            .iter()
+            .filter(|(unit, _)| !matches!(unit, Unit::Box(_)));
+        let boxes = self
+            .units
+            .iter()
+            .filter(|(unit, _)| matches!(unit, Unit::Box(_)));
+        self.intersection(Technique::Claiming, lines, boxes)
+    }
+
+    fn intersection<'a>(
+        &self,
+        technique: Technique,
+        bases: impl Iterator<Item = &'a (Unit, Vec<usize>)>,
+        covers: impl Iterator<Item = &'a (Unit, Vec<usize>)> + Clone,
+    ) -> Option<Step> {
+        bases
+            .cartesian_product(0..self.size)
+            .find_map(|((base, base_cells), number)| {
+                let positions: Vec<usize> = self
+                    .empty_cells(base_cells)
+                    .filter(|&cell| self.has_candidate(cell, number))
+                    .collect();
+                if positions.len() < 2 {
+                    return None;
+                }
+
+                covers.clone().find_map(|(cover, cover_cells)| {
+                    if cover == base || !positions.iter().all(|cell| cover_cells.contains(cell)) {
+                        return None;
+                    }
+
+                    let eliminations = cover_cells
+                        .iter()
+                        .filter(|cell| !base_cells.contains(cell))
+                        .map(|&cell| (cell, number));
+                    self.elimination_step(
+                        technique,
+                        positions.clone(),
+                        eliminations,
+                        format!(
+                            "{} in {base}: {} is confined to {cover}",
+                            technique.name(),
+                            symbol(number)
+                        ),
+                    )
+                })
+            })
+    }
+
+    // k lines whose candidates of a number lie in the same k crossing lines
+    fn fish(&self, k: usize) -> Option<Step> {
+        let technique = if k == 2 {
+            Technique::XWing
+        } else {
+            Technique::Swordfish
+        };
+
+        let rows: Vec<&(Unit, Vec<usize>)> = self
+            .units
+            .iter()
+            .filter(|(unit, _)| matches!(unit, Unit::Row(_)))
+            .collect();
+        let columns: Vec<&(Unit, Vec<usize>)> = self
+            .units
+            .iter()
+            .filter(|(unit, _)| matches!(unit, Unit::Column(_)))
+            .collect();
+
+        let step = [(&rows, &columns), (&columns, &rows)]
+            .into_iter()
+            .cartesian_product(0..self.size)
+            .find_map(|((bases, covers), number)| {
+                // for every base line, the indices of the crossing lines that hold the number
+                let crossings: Vec<(usize, Vec<usize>)> = bases
+                    .iter()
+                    .enumerate()
+                    .map(|(index, (_, cells))| {
+                        let positions = cells
+                            .iter()
+                            .enumerate()
+                            .filter(|&(_, &cell)| {
+                                self.values[cell].is_none() && self.has_candidate(cell, number)
+                            })
+                            .map(|(position, _)| position)
+                            .collect::<Vec<usize>>();
+                        (index, positions)
+                    })
+                    .filter(|(_, positions)| (2..=k).contains(&positions.len()))
+                    .collect();
+
+                crossings.iter().combinations(k).find_map(|subset| {
+                    let cover_indices: Vec<usize> = subset
+                        .iter()
+                        .flat_map(|(_, positions)| positions.iter().copied())
+                        .unique()
+                        .sorted()
+                        .collect();
+                    if cover_indices.len() != k {
+                        return None;
+                    }
+
+                    let base_indices: Vec<usize> = subset.iter().map(|(index, _)| *index).collect();
+                    let eliminations = cover_indices.iter().flat_map(|&cover| {
+                        covers[cover]
+                            .1
+                            .iter()
+                            .enumerate()
+                            .filter(|(position, _)| !base_indices.contains(position))
+                            .map(move |(_, &cell)| (cell, number))
+                    });
+                    let cells = base_indices.iter().flat_map(|&base| {
+                        cover_indices.iter().map(move |&cover| bases[base].1[cover])
+                    });
+                    self.elimination_step(
+                        technique,
+                        cells
+                            .filter(|&cell| self.has_candidate(cell, number))
+                            .collect::<Vec<usize>>(),
+                        eliminations,
+                        format!(
+                            "{} on {} in {}",
+                            technique.name(),
+                            symbol(number),
+                            base_indices.iter().map(|&base| bases[base].0).join(", ")
+                        ),
+                    )
+                })
+            });
+        step
+    }
+
+    // a pivot {x, y} with pincers {x, z} and {y, z}, one of the pincers has to be z
+    fn xy_wing(&self) -> Option<Step> {
+        let pairs: Vec<usize> = (0..self.values.len())
+            .filter(|&cell| self.candidates[cell].count_ones() == 2)
+            .collect();
+
+        pairs.iter().find_map(|&pivot| {
+            let pincers: Vec<usize> = pairs
+                .iter()
+                .copied()
+                .filter(|&cell| {
+                    self.sees(pivot, cell)
+                        && (self.candidates[pivot] & self.candidates[cell]).count_ones() == 1
+                })
+                .collect();
+
+            pincers.iter().tuple_combinations().find_map(|(&a, &b)| {
+                let z = self.candidates[a] & self.candidates[b] & !self.candidates[pivot];
+                // the pincers have to share a different number with the pivot
+                if z.count_ones() != 1
+                    || self.candidates[a] & self.candidates[pivot]
+                        == self.candidates[b] & self.candidates[pivot]
+                    || self.candidates[a] | self.candidates[b] | self.candidates[pivot]
+                        != self.candidates[pivot] | z
+                {
+                    return None;
+                }
+
+                let number = z.trailing_zeros();
+                let eliminations = (0..self.values.len())
+                    .filter(|&cell| cell != pivot && self.sees(a, cell) && self.sees(b, cell))
+                    .map(|cell| (cell, number));
+                self.elimination_step(
+                    Technique::XYWing,
+                    [pivot, a, b],
+                    eliminations,
+                    format!(
+                        "XY-Wing with pivot {} and pincers {}, {}: one of the pincers is {}",
+                        cell_name(self.position(pivot)),
+                        cell_name(self.position(a)),
+                        cell_name(self.position(b)),
+                        symbol(number)
+                    ),
+                )
+            })
+        })
+    }
+
+    // alternating chain of strong and weak links on one number, starting and ending with a strong link,
+    // so one of its ends has to hold the number
+    fn x_chain(&self) -> Option<Step> {
+        const MAX_LINKS: usize = 9;
+
+        (0..self.size).find_map(|number| {
+            let holders: Vec<usize> = (0..self.values.len())
+                .filter(|&cell| self.values[cell].is_none() && self.has_candidate(cell, number))
+                .collect();
+
+            // conjugate pairs: the only two places for the number in some unit
+            let strong_links: Vec<(usize, usize)> = self
+                .units
+                .iter()
+                .filter_map(|(_, cells)| {
+                    self.empty_cells(cells)
+                        .filter(|&cell| self.has_candidate(cell, number))
+                        .collect_tuple()
+                })
+                .flat_map(|(a, b)| [(a, b), (b, a)])
+                .unique()
+                .collect();
+
+            holders.iter().find_map(|&start| {
+                // breadth first over (cell, reached through a strong link) so every cell is
+                // visited at most once per parity, the chain is rebuilt from the parents
+                let mut parents: HashMap<(usize, bool), (usize, bool)> = HashMap::new();
+                let mut frontier = vec![(start, false)];
+                for _ in 0..MAX_LINKS {
+                    let mut next_frontier = vec![];
+                    for (cell, strong) in frontier {
+                        let neighbours: Vec<usize> = if strong {
+                            // a weak link to any other holder of the number
+                            holders
+                                .iter()
+                                .copied()
+                                .filter(|&other| self.sees(cell, other))
+                                .collect()
+                        } else {
+                            strong_links
+                                .iter()
+                                .filter(|(from, _)| *from == cell)
+                                .map(|&(_, to)| to)
+                                .collect()
+                        };
+
+                        for next in neighbours {
+                            let state = (next, !strong);
+                            if next == start || parents.contains_key(&state) {
+                                continue;
+                            }
+                            parents.insert(state, (cell, strong));
+                            next_frontier.push(state);
+                        }
+                    }
+
+                    for &(end, strong) in next_frontier.iter() {
+                        if !strong {
+                            continue;
+                        }
+                        let mut chain = vec![end];
+                        let mut state = (end, strong);
+                        while let Some(&parent) = parents.get(&state) {
+                            chain.push(parent.0);
+                            state = parent;
+                        }
+                        // shorter chains are pointing or claiming, and a chain may not cross itself
+                        if chain.len() < 4 || chain.iter().unique().count() != chain.len() {
+                            continue;
+                        }
+                        chain.reverse();
+
+                        let eliminations = holders
+                            .iter()
+                            .filter(|&&cell| {
+                                !chain.contains(&cell)
+                                    && self.sees(start, cell)
+                                    && self.sees(end, cell)
+                            })
+                            .map(|&cell| (cell, number));
+                        let step = self.elimination_step(
+                            Technique::XChain,
+                            chain.clone(),
+                            eliminations,
+                            format!(
+                                "X-Chain on {} from {} to {}: one of the ends is {}",
+                                symbol(number),
+                                cell_name(self.position(start)),
+                                cell_name(self.position(end)),
+                                symbol(number)
+                            ),
+                        );
+                        if step.is_some() {
+                            return step;
+                        }
+                    }
+                    frontier = next_frontier;
+                }
+                None
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // numbers as they are shown on the board, counted from 1
+    fn choice(row: u32, column: u32, number: u32) -> Choice {
+        Choice::new(&Shape::square(3), row, column, number - 1)
+    }
+
+    fn choices(cells: impl IntoIterator<Item = (u32, u32)>, numbers: &[u32]) -> Vec<Choice> {
+        cells
+            .into_iter()
+            .flat_map(|(row, column)| {
+                numbers
+                    .iter()
+                    .map(move |&number| choice(row, column, number))
+            })
+            .collect()
+    }
+
+    fn empty_grid() -> Grid {
+        Grid::new(&Shape::square(3), [])
+    }
+
+    fn set_candidates(grid: &mut Grid, (row, column): (u32, u32), numbers: &[u32]) {
+        grid.candidates[(row * 9 + column) as usize] = numbers
+            .iter()
+            .fold(0, |candidates, number| candidates | 1 << (number - 1));
+    }
+
+    fn eliminate(grid: &mut Grid, cells: impl IntoIterator<Item = (u32, u32)>, number: u32) {
+        for (row, column) in cells {
+            grid.eliminate(choice(row, column, number));
+        }
+    }
+
+    fn row(row: u32, columns: impl IntoIterator<Item = u32>) -> Vec<(u32, u32)> {
+        columns.into_iter().map(|column| (row, column)).collect()
+    }
+
+    fn column(column: u32, rows: impl IntoIterator<Item = u32>) -> Vec<(u32, u32)> {
+        rows.into_iter().map(|row| (row, column)).collect()
+    }
+
+    #[test]
+    fn naked_single() {
+        let grid = Grid::new(
+            &Shape::square(3),
+            (0..8).map(|column| choice(0, column, column + 1)),
+        );
+        let step = grid.next_step(Grade::Easy).unwrap();
+        assert_eq!(step.technique, Technique::NakedSingle);
+        assert_eq!(step.placement, Some(choice(0, 8, 9)));
+        assert_eq!(step.eliminations, vec![]);
+    }
+
+    #[test]
+    fn hidden_single() {
+        // the rows and columns of these 1s leave a single place for a 1 in the first row
+        let grid = Grid::new(
+            &Shape::square(3),
+            [
+                choice(1, 3, 1),
+                choice(2, 6, 1),
+                choice(3, 0, 1),
+                choice(4, 1, 1),
+            ],
+        );
+        let step = grid.next_step(Grade::Easy).unwrap();
+        assert_eq!(step.technique, Technique::HiddenSingle);
+        assert_eq!(step.placement, Some(choice(0, 2, 1)));
+    }
+
+    #[test]
+    fn naked_pair() {
+        let mut grid = empty_grid();
+        set_candidates(&mut grid, (0, 0), &[1, 2]);
+        set_candidates(&mut grid, (0, 1), &[1, 2]);
+        let step = grid.naked_subset(2).unwrap();
+        assert_eq!(step.technique, Technique::NakedPair);
+        assert_eq!(step.cells, vec![(0, 0), (0, 1)]);
+        assert_eq!(step.eliminations, choices(row(0, 2..9), &[1, 2]));
+    }
+
+    #[test]
+    fn naked_triple() {
+        let mut grid = empty_grid();
+        set_candidates(&mut grid, (0, 0), &[1, 2]);
+        set_candidates(&mut grid, (0, 1), &[2, 3]);
+        set_candidates(&mut grid, (0, 2), &[1, 3]);
+        assert_eq!(grid.naked_subset(2), None);
+        let step = grid.naked_subset(3).unwrap();
+        assert_eq!(step.technique, Technique::NakedTriple);
+        assert_eq!(step.eliminations, choices(row(0, 3..9), &[1, 2, 3]));
+    }
+
+    #[test]
+    fn hidden_pair() {
+        let mut grid = empty_grid();
+        for number in [1, 2] {
+            eliminate(&mut grid, row(0, 2..9), number);
+        }
+        let step = grid.hidden_subset(2).unwrap();
+        assert_eq!(step.technique, Technique::HiddenPair);
+        assert_eq!(step.cells, vec![(0, 0), (0, 1)]);
+        assert_eq!(
+            step.eliminations,
+            choices(row(0, 0..2), &[3, 4, 5, 6, 7, 8, 9])
+        );
+    }
+
+    #[test]
+    fn hidden_triple() {
+        let mut grid = empty_grid();
+        for number in [1, 2, 3] {
+            eliminate(&mut grid, row(0, 3..9), number);
+        }
+        assert_eq!(grid.hidden_subset(2), None);
+        let step = grid.hidden_subset(3).unwrap();
+        assert_eq!(step.technique, Technique::HiddenTriple);
+        assert_eq!(
+            step.eliminations,
+            choices(row(0, 0..3), &[4, 5, 6, 7, 8, 9])
+        );
+    }
+
+    #[test]
+    fn pointing() {
+        // the 1s of the first box are all in the first row
+        let mut grid = empty_grid();
+        eliminate(&mut grid, row(1, 0..3).into_iter().chain(row(2, 0..3)), 1);
+        let step = grid.pointing().unwrap();
+        assert_eq!(step.technique, Technique::Pointing);
+        assert_eq!(step.cells, row(0, 0..3));
+        assert_eq!(step.eliminations, choices(row(0, 3..9), &[1]));
+    }
+
+    #[test]
+    fn claiming() {
+        // the 1s of the first row are all in the first box
+        let mut grid = empty_grid();
+        eliminate(&mut grid, row(0, 3..9), 1);
+        assert_eq!(grid.pointing(), None);
+        let step = grid.claiming().unwrap();
+        assert_eq!(step.technique, Technique::Claiming);
+        assert_eq!(step.cells, row(0, 0..3));
+        assert_eq!(
+            step.eliminations,
+            choices(row(1, 0..3).into_iter().chain(row(2, 0..3)), &[1])
+        );
+    }
+
+    #[test]
+    fn x_wing() {
+        // the 1s of the second and fifth rows are in the third and seventh columns
+        let mut grid = empty_grid();
+        for line in [1, 4] {
+            eliminate(
+                &mut grid,
+                row(line, (0..9).filter(|&c| c != 2 && c != 6)),
+                1,
+            );
+        }
+        let step = grid.fish(2).unwrap();
+        assert_eq!(step.technique, Technique::XWing);
+        assert_eq!(step.cells, vec![(1, 2), (1, 6), (4, 2), (4, 6)]);
+        let others = || (0..9).filter(|&r| r != 1 && r != 4);
+        assert_eq!(
+            step.eliminations,
+            choices(
+                column(2, others()).into_iter().chain(column(6, others())),
+                &[1]
+            )
+        );
+    }
+
+    #[test]
+    fn swordfish() {
+        // the 1s of three rows are spread over three columns, two in each row
+        let mut grid = empty_grid();
+        for (line, columns) in [(0, [1, 4]), (3, [4, 7]), (6, [1, 7])] {
+            eliminate(
+                &mut grid,
+                row(line, (0..9).filter(|c| !columns.contains(c))),
+                1,
+            );
+        }
+        assert_eq!(grid.fish(2), None);
+        let step = grid.fish(3).unwrap();
+        assert_eq!(step.technique, Technique::Swordfish);
+        let others = || (0..9).filter(|r| ![0, 3, 6].contains(r));
+        assert_eq!(
+            step.eliminations,
+            choices(
+                [1, 4, 7].into_iter().flat_map(|c| column(c, others())),
+                &[1]
+            )
+        );
+    }
+
+    #[test]
+    fn xy_wing() {
+        // whether the pivot is 1 or 2, one of the pincers is 3
+        let mut grid = empty_grid();
+        set_candidates(&mut grid, (0, 0), &[1, 2]);
+        set_candidates(&mut grid, (0, 4), &[1, 3]);
+        set_candidates(&mut grid, (4, 0), &[2, 3]);
+        let step = grid.xy_wing().unwrap();
+        assert_eq!(step.technique, Technique::XYWing);
+        assert_eq!(step.cells, vec![(0, 0), (0, 4), (4, 0)]);
+        // the only cell that sees both pincers
+        assert_eq!(step.eliminations, vec![choice(4, 4, 3)]);
+    }
+
+    #[test]
+    fn x_chain() {
+        // the first and fifth rows only have two places for a 1, and the ends in the sixth
+        // column see each other, so r1c1 or r5c2 is 1
+        let mut grid = empty_grid();
+        eliminate(&mut grid, row(0, (1..9).filter(|&c| c != 5)), 1);
+        eliminate(&mut grid, row(4, (0..9).filter(|&c| c != 1 && c != 5)), 1);
+        let step = grid.x_chain().unwrap();
+        assert_eq!(step.technique, Technique::XChain);
+        assert_eq!(step.cells, vec![(0, 0), (0, 5), (4, 5), (4, 1)]);
+        assert_eq!(
+            step.eliminations,
+            choices([(1, 1), (2, 1), (3, 0), (5, 0)], &[1])
+        );
+    }
+
+    #[test]
+    fn the_hardest_technique_grades_a_trace() {
+        let step = |technique| Step {
+            technique,
+            cells: vec![],
+            placement: None,
+            eliminations: vec![],
+            description: String::new(),
+        };
+        let trace = |techniques: &[Technique], solved| Trace {
+            steps: techniques.iter().copied().map(step).collect(),
+            solved,
+        };
+        assert_eq!(trace(&[], true).grade(), Some(Grade::Easy));
+        assert_eq!(
+            trace(
+                &[
+                    Technique::HiddenSingle,
+                    Technique::XWing,
+                    Technique::NakedPair
+                ],
+                true
+            )
+            .grade(),
+            Some(Grade::Hard)
+        );
+        assert_eq!(trace(&[Technique::NakedSingle], false).grade(), None);
+    }
+
+    // the packs are named after the grade of their puzzles
+    #[test]
+    fn pack_puzzles_keep_their_grade() {
+        let packs = [
+            (Grade::Easy, include_str!("../assets/packs/easy.sdm")),
+            (Grade::Medium, include_str!("../assets/packs/medium.sdm")),
+            (Grade::Hard, include_str!("../assets/packs/hard.sdm")),
+            (Grade::Expert, include_str!("../assets/packs/expert.sdm")),
+        ];
+        for (grade, text) in packs {
+            for puzzle in crate::format::Format::Sdm.parse(text).unwrap() {
+                let trace = rate(&puzzle.shape, puzzle.givens);
+                assert_eq!(
+                    trace.grade(),
+                    Some(grade),
+                    "line {} of the {} pack",
+                    puzzle.line,
+                    grade.name()
+                );
+            }
+        }
+    }
+}
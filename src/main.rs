@@ -1,34 +1,26 @@
-use bevy::{
-    ecs::removal_detection, prelude::*, render::render_resource::FilterMode,
-    utils::tracing::instrument::WithSubscriber,
-};
-use bevy_defer::{
-    async_system, async_systems::AsyncSystem, cancellation::Cancellation, signal_ids,
-    signals::Sender, AsyncCommandsExtension, AsyncPlugin,
-};
+use bevy::prelude::*;
+use bevy_defer::{signal_ids, AsyncPlugin};
 use bevy_ecs_tilemap::{
-    helpers::{filling::fill_tilemap, geometry::get_tilemap_center_transform},
-    map::{TilemapGridSize, TilemapId, TilemapSize, TilemapTexture, TilemapTileSize, TilemapType},
+    map::{TilemapTexture, TilemapTileSize},
     prelude::{ArrayTextureLoader, TilemapArrayTexture},
-    tiles::{TileBundle, TilePos, TileStorage, TileTextureIndex},
-    TilemapBundle, TilemapPlugin,
+    TilemapPlugin,
 };
-use bevy_inspector_egui::{egui::debug_text::print, quick::StateInspectorPlugin};
 use rand::{thread_rng, Rng};
-use std::fs::File;
-use std::{rc::Rc, time::Instant};
 use sudoku::Solution;
 
-use crate::{
-    exact_cover::SolvingState,
-    sudoku::{Choice, Sudoku},
-};
-use rand::prelude::SliceRandom;
+use crate::board::{Board, TileTexture};
+mod board;
 mod camera;
-mod dancing_links;
-mod exact_cover;
+mod clipboard;
 mod helpers;
-mod sudoku;
+mod hint;
+mod history;
+mod input;
+mod menu;
+mod notes;
+mod packs;
+mod save;
+mod win;
 
 fn main() {
     App::new()
@@ -37,19 +29,91 @@ fn main() {
             DefaultPlugins.set(ImagePlugin::default_nearest()),
             TilemapPlugin,
         ))
-        .add_systems(Startup, (setup, (generate_board).after(setup)))
+        .init_asset::<packs::PuzzlePack>()
+        .register_asset_loader(packs::PuzzlePackLoader)
+        .add_event::<packs::LoadPuzzle>()
+        .add_systems(
+            Startup,
+            (
+                setup,
+                (generate_board).after(setup),
+                packs::load_packs,
+                menu::spawn_menu,
+            ),
+        )
         .add_systems(Update, camera::movement)
+        .add_systems(
+            OnEnter(win::GameState::Won),
+            (win::show_win, save::remove, packs::mark_solved),
+        )
+        .add_systems(OnExit(win::GameState::Won), win::hide_win)
+        .add_systems(
+            Last,
+            save::save_on_exit
+                .run_if(resource_exists::<Board>.and_then(in_state(win::GameState::Playing))),
+        )
+        .add_systems(
+            Update,
+            (
+                (
+                    (notes::toggle_notes_mode, win::tick_elapsed_time),
+                    (
+                        input::select_with_mouse,
+                        input::move_selection,
+                        input::enter_number,
+                        notes::fill_notes,
+                        history::undo_redo,
+                    ),
+                    (hint::clear_hint, hint::request_hint),
+                )
+                    .chain()
+                    .run_if(in_state(win::GameState::Playing)),
+                (
+                    clipboard::copy_paste,
+                    packs::play_daily,
+                    packs::poll_daily,
+                    packs::play_next_pack_puzzle,
+                    menu::choose_size,
+                    menu::toggle_jigsaw,
+                    menu::toggle_variant,
+                ),
+                packs::load_puzzle,
+                board::rebuild_tilemaps.run_if(resource_changed::<Board>),
+                (
+                    board::draw_digits,
+                    board::draw_notes,
+                    (win::check_win, save::save_game)
+                        .run_if(in_state(win::GameState::Playing)),
+                )
+                    .run_if(resource_changed::<Board>),
+                (board::paint_cells, packs::show_source),
+            )
+                .chain()
+                .run_if(resource_exists::<Board>),
+        )
 
         .init_resource::<camera::CameraControl>()
+        .init_resource::<BoardSeed>()
+        .init_resource::<menu::Settings>()
+        .init_resource::<hint::Hint>()
+        .init_resource::<board::Selection>()
+        .init_resource::<notes::NotesMode>()
+        .init_resource::<history::History>()
+        .init_resource::<win::ElapsedTime>()
+        .init_state::<win::GameState>()
             // .add_systems(Update, (show_solution))
         .run();
 }
 
-#[derive(Component)]
-struct SudokuBoardFG;
+// seed a new board is generated from when there is no game to resume
+#[derive(Resource)]
+struct BoardSeed(u64);
 
-#[derive(Component)]
-struct SudokuBoardBG;
+impl Default for BoardSeed {
+    fn default() -> Self {
+        Self(thread_rng().gen())
+    }
+}
 
 signal_ids! {
     OnSolutionFound: Solution,
@@ -61,164 +125,138 @@ fn setup(
     array_texture_loader: Res<ArrayTextureLoader>,
 ) {
     commands.spawn(Camera2dBundle::default());
-    let board_size = TilemapSize::new(9, 9);
-
-    let tile_size = TilemapTileSize::new(16. * 2., 16. * 2.);
-    let grid_size = TilemapGridSize::new(16. * 2., 16. * 2.);
-    let map_type = TilemapType::default();
+    commands.spawn((
+        packs::SourceText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.,
+                color: Color::WHITE,
+                ..default()
+            },
+        ),
+    ));
+    commands.spawn((
+        hint::HintText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(5.),
+            left: Val::Px(5.),
+            ..default()
+        }),
+    ));
+    commands.spawn((
+        notes::NotesText,
+        TextBundle::from_section(
+            "Notes: off",
+            TextStyle {
+                font_size: 24.,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(5.),
+            right: Val::Px(5.),
+            ..default()
+        }),
+    ));
+    commands.spawn((
+        clipboard::MessageText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(5.),
+            right: Val::Px(5.),
+            ..default()
+        }),
+    ));
+    commands.spawn((
+        win::WinText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 48.,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(5.),
+            left: Val::Percent(45.),
+            ..default()
+        }),
+    ));
     let texture_handle = asset_server.load("test_wfc.png");
-
     array_texture_loader.add(TilemapArrayTexture {
         texture: TilemapTexture::Single(texture_handle.clone()),
         tile_size: TilemapTileSize::new(16., 16.),
         ..Default::default()
     });
-
-    let board_fg_entity = commands.spawn_empty().id();
-    let board_fg_id = TilemapId(board_fg_entity);
-    let mut board_fg_tile_storage = TileStorage::empty(board_size);
-
-    fill_tilemap(
-        TileTextureIndex((26 * 4) + 10),
-        board_size,
-        board_fg_id,
-        &mut commands,
-        &mut board_fg_tile_storage,
-    );
-
-    commands
-        .entity(board_fg_entity)
-        .insert(TilemapBundle {
-            grid_size,
-            size: board_size,
-            map_type,
-            texture: TilemapTexture::Single(texture_handle.clone()),
-            tile_size,
-            storage: board_fg_tile_storage,
-            transform: get_tilemap_center_transform(&board_size, &grid_size, &map_type, 0.),
-            ..Default::default()
-        })
-        .insert(SudokuBoardFG);
-
-    let board_bg_entity = commands.spawn_empty().id();
-    let board_bg_id = TilemapId(board_bg_entity);
-    let mut board_bg_tile_storage = TileStorage::empty(board_size);
-
-    fill_tilemap(
-        TileTextureIndex(27),
-        board_size,
-        board_bg_id,
-        &mut commands,
-        &mut board_bg_tile_storage,
-    );
-
-    commands
-        .entity(board_bg_entity)
-        .insert(TilemapBundle {
-            grid_size,
-            size: board_size,
-            map_type,
-            texture: TilemapTexture::Single(texture_handle.clone()),
-            tile_size,
-            storage: board_bg_tile_storage,
-            transform: get_tilemap_center_transform(&board_size, &grid_size, &map_type, 1.),
-            ..Default::default()
-        })
-        .insert(SudokuBoardBG);
+    commands.insert_resource(TileTexture(texture_handle));
 }
 
 fn generate_board(
-    board_fg_query: Query<(&TilemapSize, &TileStorage), With<SudokuBoardFG>>,
-    board_bg_query: Query<(&TilemapSize, &TileStorage), With<SudokuBoardBG>>,
-    mut tile_query: Query<&mut TileTextureIndex>,
+    mut commands: Commands,
+    seed: Res<BoardSeed>,
+    mut settings: ResMut<menu::Settings>,
+    mut message_query: Query<&mut Text, With<clipboard::MessageText>>,
 ) {
-    let mut n = 0;
-    if let Ok((tilemap_size, _)) = board_fg_query.get_single() {
-        n = (tilemap_size.x as f64).sqrt() as u32;
-    }
-
-    let mut filled_board: Solution = vec![];
-    let mut removed_choices = vec![];
-    let sudoku = Sudoku::new(n, vec![]);
-
-    sudoku.solve(|solution| {
-        filled_board = solution;
-        return SolvingState::Abort;
-    });
-
-    for choice in filled_board.clone().into_iter() {
-        removed_choices.push(choice);
-        let now = Instant::now();
-
-        let board = filled_board
-            .iter()
-            .copied()
-            .filter(|c| !removed_choices.contains(c))
-            .collect::<Vec<Choice>>();
-        let sudoku = Sudoku::new(n, board);
-
-        let mut solutions_found = 0;
-        sudoku.solve(|_| {
-            solutions_found += 1;
-
-            if solutions_found <= 1 {
-                return SolvingState::Continue;
-            } else {
-                return SolvingState::Abort;
-            }
+    // resume the last game if it was left unfinished
+    if let Some(game) = save::load() {
+        commands.insert_resource(game.source);
+        commands.insert_resource(Board {
+            shape: game.shape,
+            givens: game.givens,
+            entries: game.entries,
+            notes: game.notes,
         });
-        println!("{}", now.elapsed().as_millis());
-        println!("found {} solutions", solutions_found);
-
-        if solutions_found > 1 {
-            removed_choices.pop();
-        }
+        commands.insert_resource(game.history);
+        commands.insert_resource(win::ElapsedTime(game.elapsed));
+        return;
     }
 
-    let mut solution = filled_board.clone();
-
-    for choice in removed_choices {
-        solution.remove(solution.iter().position(|c| *c == choice).unwrap());
-    }
-
-    if let Ok((_, tile_storage)) = board_fg_query.get_single() {
-        for choice in solution {
-            let pos = TilePos {
-                x: choice.row as u32,
-                y: choice.column as u32,
-            };
-            let texture_offset = if n <= 3 { 26 * 4 } else { 26 * 3 };
-            if let Some(tile_entity) = tile_storage.get(&pos) {
-                if let Ok(mut tile) = tile_query.get_mut(tile_entity) {
-                    tile.0 = texture_offset + (choice.number as u32);
-                }
-            }
-        }
-    }
-
-    if let Ok((tilemap_size, tile_storage)) = board_bg_query.get_single() {
-        let n = (tilemap_size.x as f64).sqrt() as u32;
-        for x in 0..tilemap_size.x {
-            for y in 0..tilemap_size.y {
-                let pos = TilePos { x, y };
-                if let Some(tile_entity) = tile_storage.get(&pos) {
-                    if let Ok(mut tile) = tile_query.get_mut(tile_entity) {
-                        let x_offset = match x % n {
-                            0 => 0,
-                            nx if nx == n - 1 => 2,
-                            _ => 1,
-                        };
-                        let y_offset = match y % n {
-                            0 => 0,
-                            ny if ny == n - 1 => 2,
-                            _ => 1,
-                        };
-
-                        tile.0 = 2 * 26 + x_offset - (y_offset * 26);
-                    }
-                }
+    // the menu only keeps settings that make a board, unless the file is older or was edited
+    // by hand, then the board starts without variants and so do the settings
+    let (puzzle, message) = match menu::puzzle(&settings, seed.0) {
+        Ok(puzzle) => (Some(puzzle), String::new()),
+        Err(message) => {
+            menu::drop_variants(&mut settings);
+            match menu::puzzle(&settings, seed.0) {
+                Ok(puzzle) => (
+                    Some(puzzle),
+                    format!("{message}, started a board without variants"),
+                ),
+                Err(error) => (None, format!("{message}. {error}")),
             }
         }
+    };
+    // an empty board still lets the player paste a puzzle or choose other settings
+    let board = match puzzle {
+        Some(puzzle) => Board::new(puzzle.shape, puzzle.givens),
+        None => Board::new(settings.shape.clone(), vec![]),
+    };
+    commands.insert_resource(board);
+    commands.insert_resource(packs::PuzzleSource::Random { seed: seed.0 });
+    if let Ok(mut text) = message_query.get_single_mut() {
+        text.sections[0].value = message;
     }
 }
 
@@ -0,0 +1,369 @@
+use bevy::prelude::*;
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+use sudoku::{GenerateError, Generator, Puzzle, Shape, Symmetry, Variant};
+
+use crate::clipboard::MessageText;
+use crate::packs::{LoadPuzzle, PuzzleSource};
+use crate::save::{read_data, write_data};
+
+const SETTINGS_FILE: &str = "settings.ron";
+
+// board sizes offered in the menu, each played with the usual boxes for its size
+const BOARD_SIZES: [u32; 6] = [4, 6, 9, 12, 16, 25];
+
+// variants offered in the menu with the largest board size each is offered for, filling a
+// 25x25 windoku board takes longer than the fill steps allow
+const VARIANTS: [(Variant, u32); 4] = [
+    (Variant::Diagonal, 25),
+    (Variant::Windoku, 16),
+    (Variant::AntiKnight, 25),
+    (Variant::AntiKing, 25),
+];
+
+// search steps per cell the board may take to fill before generating gives up
+const FILL_STEPS_PER_CELL: u64 = 20_000;
+
+const BUTTON_COLOR: Color = Color::rgb(0.25, 0.25, 0.3);
+const BUTTON_HOVERED_COLOR: Color = Color::rgb(0.35, 0.35, 0.45);
+const BUTTON_ACTIVE_COLOR: Color = Color::rgb(0.3, 0.4, 0.7);
+
+// choices of the player that outlive a single game
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct Settings {
+    // boxes of new random boards
+    pub shape: Shape,
+    // new random boards get irregular regions instead of boxes
+    #[serde(default)]
+    pub jigsaw: bool,
+    // extra rules of new random boards
+    #[serde(default)]
+    pub variants: Vec<Variant>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        read_data(SETTINGS_FILE).unwrap_or(Self {
+            shape: Shape::default(),
+            jigsaw: false,
+            variants: vec![],
+        })
+    }
+}
+
+impl Settings {
+    // size and rules of the boards, like "9x9 jigsaw diagonal"
+    pub fn kind(&self) -> String {
+        let size = self.shape.size();
+        let mut kind = format!("{size}x{size}");
+        if self.jigsaw {
+            kind.push_str(" jigsaw");
+        }
+        for variant in &self.variants {
+            kind.push(' ');
+            kind.push_str(variant.name());
+        }
+        kind
+    }
+}
+
+// starts a random board with boxes of this shape when pressed
+#[derive(Component)]
+pub struct SizeButton(pub Shape);
+
+// switches between boxes and jigsaw regions and starts a new random board
+#[derive(Component)]
+pub struct JigsawButton;
+
+// adds or removes the rules of a variant and starts a new random board
+#[derive(Component)]
+pub struct VariantButton(pub Variant);
+
+// the same seed gives the same puzzle on every machine, so the limits count work instead of time:
+// some combinations of variants take long to fill and are given up, and removing clues from big
+// boards stops early with more clues left
+pub fn generator(settings: &Settings, seed: u64) -> Generator {
+    let size = settings.shape.size();
+    Generator {
+        symmetry: Symmetry::Rotational,
+        fill_steps: Some(FILL_STEPS_PER_CELL * u64::from(settings.shape.cells())),
+        max_removals: (size > 9).then_some(5 * size as usize),
+        jigsaw: settings.jigsaw,
+        ..Generator::new(
+            settings
+                .shape
+                .with_variants(settings.variants.iter().copied()),
+            seed,
+        )
+    }
+}
+
+// a puzzle of the size and kind the player chose, or the message why there is none
+pub fn puzzle(settings: &Settings, seed: u64) -> Result<Puzzle, String> {
+    generate(settings, generator(settings, seed))
+}
+
+// runs a generator made from the settings, see generator
+pub fn generate(settings: &Settings, generator: Generator) -> Result<Puzzle, String> {
+    let size = settings.shape.size();
+    if let Some((variant, largest)) = unoffered_variant(settings) {
+        return Err(format!(
+            "{} is only offered up to {largest}x{largest} boards",
+            variant_label(variant)
+        ));
+    }
+    generator.generate().map_err(|error| match error {
+        GenerateError::NoBoard => {
+            format!("No {size}x{size} board follows the rules of every chosen variant")
+        }
+        GenerateError::GaveUp => {
+            format!("Could not fill a {size}x{size} board with these variants, try again")
+        }
+    })
+}
+
+// a random puzzle of the size and kind the player chose
+fn random_puzzle(settings: &Settings) -> Result<LoadPuzzle, String> {
+    let seed = thread_rng().gen();
+    let puzzle = puzzle(settings, seed)?;
+    Ok(LoadPuzzle {
+        shape: puzzle.shape,
+        givens: puzzle.givens,
+        source: PuzzleSource::Random { seed },
+    })
+}
+
+// a chosen variant not offered for the board size, with the largest size it is offered for
+fn unoffered_variant(settings: &Settings) -> Option<(Variant, u32)> {
+    VARIANTS.into_iter().find(|&(variant, largest)| {
+        settings.shape.size() > largest && settings.variants.contains(&variant)
+    })
+}
+
+// forgets the variants of settings that make no board, so the menu, the seed and the progress
+// of daily puzzles match the board started without them
+pub fn drop_variants(settings: &mut Settings) {
+    settings.variants.clear();
+    write_data(SETTINGS_FILE, settings);
+}
+
+// starts a random board with the changed settings, and only keeps them when there is one
+fn change_settings(
+    settings: &mut Settings,
+    changed: Settings,
+    events: &mut EventWriter<LoadPuzzle>,
+    message_query: &mut Query<&mut Text, With<MessageText>>,
+) {
+    let message = match random_puzzle(&changed) {
+        Ok(puzzle) => {
+            *settings = changed;
+            write_data(SETTINGS_FILE, settings);
+            events.send(puzzle);
+            String::new()
+        }
+        Err(message) => message,
+    };
+    if let Ok(mut text) = message_query.get_single_mut() {
+        text.sections[0].value = message;
+    }
+}
+
+fn spawn_button(menu: &mut ChildBuilder, label: String, color: Color, button: impl Bundle) {
+    menu.spawn((
+        button,
+        ButtonBundle {
+            style: Style {
+                padding: UiRect::axes(Val::Px(8.), Val::Px(4.)),
+                ..default()
+            },
+            background_color: color.into(),
+            ..default()
+        },
+    ))
+    .with_children(|button| {
+        button.spawn(TextBundle::from_section(
+            label,
+            TextStyle {
+                font_size: 20.,
+                color: Color::WHITE,
+                ..default()
+            },
+        ));
+    });
+}
+
+fn toggle_color(active: bool) -> Color {
+    if active {
+        BUTTON_ACTIVE_COLOR
+    } else {
+        BUTTON_COLOR
+    }
+}
+
+pub fn spawn_menu(mut commands: Commands, settings: Res<Settings>) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(35.),
+                left: Val::Px(5.),
+                column_gap: Val::Px(5.),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|menu| {
+            for shape in BOARD_SIZES.into_iter().filter_map(Shape::for_size) {
+                let size = shape.size();
+                spawn_button(
+                    menu,
+                    format!("{size}x{size}"),
+                    BUTTON_COLOR,
+                    SizeButton(shape),
+                );
+            }
+            spawn_button(
+                menu,
+                "Jigsaw".to_string(),
+                toggle_color(settings.jigsaw),
+                JigsawButton,
+            );
+            for (variant, _) in VARIANTS {
+                spawn_button(
+                    menu,
+                    variant_label(variant).to_string(),
+                    toggle_color(settings.variants.contains(&variant)),
+                    VariantButton(variant),
+                );
+            }
+        });
+}
+
+pub fn choose_size(
+    mut button_query: Query<
+        (&Interaction, &SizeButton, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+    mut settings: ResMut<Settings>,
+    mut events: EventWriter<LoadPuzzle>,
+    mut message_query: Query<&mut Text, With<MessageText>>,
+) {
+    for (interaction, button, mut color) in button_query.iter_mut() {
+        *color = match interaction {
+            Interaction::Hovered => BUTTON_HOVERED_COLOR.into(),
+            _ => BUTTON_COLOR.into(),
+        };
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let changed = Settings {
+            shape: button.0.clone(),
+            ..settings.clone()
+        };
+        change_settings(&mut settings, changed, &mut events, &mut message_query);
+    }
+}
+
+pub fn toggle_jigsaw(
+    mut button_query: Query<
+        (&Interaction, &JigsawButton, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+    mut settings: ResMut<Settings>,
+    mut events: EventWriter<LoadPuzzle>,
+    mut message_query: Query<&mut Text, With<MessageText>>,
+) {
+    for (interaction, _, mut color) in button_query.iter_mut() {
+        if *interaction == Interaction::Pressed {
+            let changed = Settings {
+                jigsaw: !settings.jigsaw,
+                ..settings.clone()
+            };
+            change_settings(&mut settings, changed, &mut events, &mut message_query);
+        }
+
+        *color = match interaction {
+            Interaction::Hovered => BUTTON_HOVERED_COLOR.into(),
+            _ => toggle_color(settings.jigsaw).into(),
+        };
+    }
+}
+
+fn variant_label(variant: Variant) -> &'static str {
+    match variant {
+        Variant::Diagonal => "Diagonal",
+        Variant::Windoku => "Windoku",
+        Variant::AntiKnight => "Anti-knight",
+        Variant::AntiKing => "Anti-king",
+    }
+}
+
+pub fn toggle_variant(
+    mut button_query: Query<
+        (&Interaction, &VariantButton, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+    mut settings: ResMut<Settings>,
+    mut events: EventWriter<LoadPuzzle>,
+    mut message_query: Query<&mut Text, With<MessageText>>,
+) {
+    for (interaction, button, mut color) in button_query.iter_mut() {
+        if *interaction == Interaction::Pressed {
+            let mut changed = settings.clone();
+            match changed
+                .variants
+                .iter()
+                .position(|&variant| variant == button.0)
+            {
+                Some(index) => {
+                    changed.variants.remove(index);
+                }
+                None => changed.variants.push(button.0),
+            }
+            change_settings(&mut settings, changed, &mut events, &mut message_query);
+        }
+
+        *color = match interaction {
+            Interaction::Hovered => BUTTON_HOVERED_COLOR.into(),
+            _ => toggle_color(settings.variants.contains(&button.0)).into(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the menu only keeps settings that make a board, so an offered variant that runs out of
+    // fill steps could never be chosen
+    #[test]
+    fn offered_variants_fill_within_the_fill_steps() {
+        for (variant, largest) in VARIANTS {
+            let shapes = BOARD_SIZES
+                .into_iter()
+                .filter(|&size| size <= largest)
+                .filter_map(Shape::for_size);
+            for shape in shapes {
+                let settings = Settings {
+                    shape,
+                    jigsaw: false,
+                    variants: vec![variant],
+                };
+                for seed in 0..3 {
+                    let generated = Generator {
+                        max_removals: Some(0),
+                        ..generator(&settings, seed)
+                    }
+                    .generate();
+                    assert_ne!(
+                        generated.err(),
+                        Some(GenerateError::GaveUp),
+                        "{} with seed {seed}",
+                        settings.kind()
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use bevy::prelude::*;
+use sudoku::Grid;
+
+use crate::board::Board;
+use crate::history::{Command, History};
+
+#[derive(Component)]
+pub struct NotesText;
+
+// while on, numbers typed by the player are noted as candidates instead of placed
+#[derive(Resource, Default)]
+pub struct NotesMode(pub bool);
+
+pub fn toggle_notes_mode(
+    input_keyboard: Res<ButtonInput<KeyCode>>,
+    mut notes_mode: ResMut<NotesMode>,
+    mut query: Query<&mut Text, With<NotesText>>,
+) {
+    if input_keyboard.just_pressed(KeyCode::Space) {
+        notes_mode.0 = !notes_mode.0;
+    }
+    if !notes_mode.is_changed() {
+        return;
+    }
+
+    if let Ok(mut text) = query.get_single_mut() {
+        text.sections[0].value = format!("Notes: {}", if notes_mode.0 { "on" } else { "off" });
+    }
+}
+
+// notes every candidate that is left in the empty cells, replacing what the player noted
+pub fn fill_notes(
+    input_keyboard: Res<ButtonInput<KeyCode>>,
+    mut board: ResMut<Board>,
+    mut history: ResMut<History>,
+) {
+    if !input_keyboard.just_pressed(KeyCode::F2) {
+        return;
+    }
+
+    let grid = Grid::new(&board.shape, board.choices().collect::<Vec<_>>());
+    let size = board.size();
+    let after = (0..size)
+        .flat_map(|row| (0..size).map(move |column| (row, column)))
+        .map(|(row, column)| {
+            grid.candidates(row, column)
+                .fold(0, |notes, number| notes | 1 << number)
+        })
+        .collect();
+
+    let command = Command::FillNotes {
+        before: board.notes.clone(),
+        after,
+    };
+    history.execute(command, &mut board);
+}
@@ -0,0 +1,341 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt,
+    io::{Error, ErrorKind},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext, LoadedFolder},
+    prelude::*,
+    reflect::TypePath,
+    tasks::{block_on, poll_once, AsyncComputeTaskPool, Task},
+    utils::BoxedFuture,
+};
+use serde::{Deserialize, Serialize};
+use sudoku::{Choice, Format, Generator, Grade, ParsedPuzzle, Puzzle, Shape};
+
+use crate::board::{Board, Selection};
+use crate::clipboard::MessageText;
+use crate::history::History;
+use crate::menu;
+use crate::save::{read_data, write_data};
+use crate::win::{ElapsedTime, GameState};
+
+const PROGRESS_FILE: &str = "progress.ron";
+
+#[derive(Component)]
+pub struct SourceText;
+
+// a collection of puzzles in one of the text formats, loaded from assets/packs
+#[derive(Asset, TypePath)]
+pub struct PuzzlePack {
+    pub name: String,
+    pub puzzles: Vec<ParsedPuzzle>,
+}
+
+pub struct PuzzlePackLoader;
+
+impl AssetLoader for PuzzlePackLoader {
+    type Asset = PuzzlePack;
+    type Settings = ();
+    type Error = Error;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<PuzzlePack, Self::Error>> {
+        Box::pin(async move {
+            let mut text = String::new();
+            reader.read_to_string(&mut text).await?;
+
+            let path = load_context.path();
+            let format = path
+                .extension()
+                .and_then(|extension| Format::from_extension(&extension.to_string_lossy()))
+                .unwrap_or_else(|| Format::detect(&text));
+            let puzzles = format
+                .parse(&text)
+                .map_err(|error| Error::new(ErrorKind::InvalidData, error))?;
+
+            Ok(PuzzlePack {
+                name: path
+                    .file_stem()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                puzzles,
+            })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        static EXTENSIONS: &[&str] = &["sdm", "sdk"];
+        EXTENSIONS
+    }
+}
+
+// holds on to the packs folder so the packs stay loaded
+#[derive(Resource)]
+pub struct PuzzlePacks(pub Handle<LoadedFolder>);
+
+// where the puzzle on the board came from
+#[derive(Resource, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum PuzzleSource {
+    Random {
+        seed: u64,
+    },
+    Pasted,
+    // kind is the size and rules of the board, one daily puzzle is played for every kind
+    Daily {
+        date: String,
+        #[serde(default)]
+        kind: String,
+    },
+    Pack {
+        name: String,
+        index: usize,
+    },
+}
+
+// the daily puzzle being generated, grading a big board takes seconds so it is done off the
+// main thread
+#[derive(Resource)]
+pub struct DailyTask {
+    source: PuzzleSource,
+    task: Task<Result<Puzzle, String>>,
+}
+
+// puzzles the player solved, kept across games
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub struct Progress {
+    // indices of the solved puzzles of every pack
+    pub packs: HashMap<String, BTreeSet<usize>>,
+    // date and kind of the solved daily puzzles, like "2024-05-01 9x9 diagonal"
+    pub days: BTreeSet<String>,
+}
+
+// replaces the board with a fresh puzzle
+#[derive(Event)]
+pub struct LoadPuzzle {
+    pub shape: Shape,
+    pub givens: Vec<Choice>,
+    pub source: PuzzleSource,
+}
+
+impl fmt::Display for PuzzleSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PuzzleSource::Random { seed } => write!(f, "Seed: {seed}"),
+            PuzzleSource::Pasted => write!(f, "Pasted puzzle"),
+            PuzzleSource::Daily { date, kind } => write!(f, "Daily {kind} puzzle {date}"),
+            PuzzleSource::Pack { name, index } => write!(f, "Pack {name}, puzzle {}", index + 1),
+        }
+    }
+}
+
+pub fn load_packs(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(PuzzlePacks(asset_server.load_folder("packs")));
+    commands.insert_resource(read_data::<Progress>(PROGRESS_FILE).unwrap_or_default());
+}
+
+// today in UTC as year, month and day, so everyone gets the same daily puzzle
+fn today() -> (u64, u64, u64) {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs() / 86400);
+
+    // days since 1970-01-01 to a civil date, counted in 400 year eras starting on 0000-03-01
+    let days = days + 719468;
+    let era = days / 146097;
+    let day_of_era = days % 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+// F5 plays the daily puzzle of the size and kind chosen in the menu, once it is generated
+pub fn play_daily(
+    mut commands: Commands,
+    input_keyboard: Res<ButtonInput<KeyCode>>,
+    settings: Res<menu::Settings>,
+    mut message_query: Query<&mut Text, With<MessageText>>,
+) {
+    if input_keyboard.just_pressed(KeyCode::F5) {
+        let (year, month, day) = today();
+        let settings = settings.clone();
+        let source = PuzzleSource::Daily {
+            date: format!("{year}-{month:02}-{day:02}"),
+            kind: settings.kind(),
+        };
+        let task = AsyncComputeTaskPool::get().spawn(async move {
+            let generator = Generator {
+                target_grade: Some(Grade::Medium),
+                ..menu::generator(&settings, year * 10000 + month * 100 + day)
+            };
+            menu::generate(&settings, generator)
+        });
+
+        // pressing F5 again drops the task of the last press, which cancels it
+        commands.insert_resource(DailyTask { source, task });
+        if let Ok(mut text) = message_query.get_single_mut() {
+            text.sections[0].value = "Generating the daily puzzle".to_string();
+        }
+    }
+}
+
+// loads the daily puzzle once it is generated, or shows why there is none
+pub fn poll_daily(
+    mut commands: Commands,
+    daily: Option<ResMut<DailyTask>>,
+    mut events: EventWriter<LoadPuzzle>,
+    mut message_query: Query<&mut Text, With<MessageText>>,
+) {
+    let Some(mut daily) = daily else {
+        return;
+    };
+    let Some(generated) = block_on(poll_once(&mut daily.task)) else {
+        return;
+    };
+
+    commands.remove_resource::<DailyTask>();
+    let message = match generated {
+        Ok(puzzle) => {
+            events.send(LoadPuzzle {
+                shape: puzzle.shape,
+                givens: puzzle.givens,
+                source: daily.source.clone(),
+            });
+            String::new()
+        }
+        Err(message) => message,
+    };
+    if let Ok(mut text) = message_query.get_single_mut() {
+        text.sections[0].value = message;
+    }
+}
+
+// F6 plays the next puzzle of the packs that is not solved yet
+pub fn play_next_pack_puzzle(
+    input_keyboard: Res<ButtonInput<KeyCode>>,
+    source: Res<PuzzleSource>,
+    progress: Res<Progress>,
+    packs: Res<Assets<PuzzlePack>>,
+    mut events: EventWriter<LoadPuzzle>,
+) {
+    if input_keyboard.just_pressed(KeyCode::F6) {
+        let mut packs: Vec<&PuzzlePack> = packs.iter().map(|(_, pack)| pack).collect();
+        packs.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let puzzles: Vec<(&str, usize, &ParsedPuzzle)> = packs
+            .iter()
+            .flat_map(|pack| {
+                pack.puzzles
+                    .iter()
+                    .enumerate()
+                    .map(|(index, puzzle)| (pack.name.as_str(), index, puzzle))
+            })
+            .collect();
+        if puzzles.is_empty() {
+            return;
+        }
+
+        // start right after the puzzle on the board, and go around once
+        let current = puzzles.iter().position(|&(name, index, _)| {
+            *source
+                == PuzzleSource::Pack {
+                    name: name.to_string(),
+                    index,
+                }
+        });
+        let start = current.map_or(0, |current| current + 1);
+        let solved = |name: &str, index: usize| {
+            progress
+                .packs
+                .get(name)
+                .is_some_and(|solved| solved.contains(&index))
+        };
+        let (name, index, puzzle) = (0..puzzles.len())
+            .map(|offset| puzzles[(start + offset) % puzzles.len()])
+            .find(|&(name, index, _)| !solved(name, index))
+            .unwrap_or(puzzles[start % puzzles.len()]);
+
+        events.send(LoadPuzzle {
+            shape: puzzle.shape.clone(),
+            givens: puzzle.givens.clone(),
+            source: PuzzleSource::Pack {
+                name: name.to_string(),
+                index,
+            },
+        });
+    }
+}
+
+pub fn load_puzzle(
+    mut events: EventReader<LoadPuzzle>,
+    mut board: ResMut<Board>,
+    mut history: ResMut<History>,
+    mut elapsed: ResMut<ElapsedTime>,
+    mut source: ResMut<PuzzleSource>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut selection: ResMut<Selection>,
+) {
+    let Some(event) = events.read().last() else {
+        return;
+    };
+
+    *board = Board::new(event.shape.clone(), event.givens.clone());
+    *history = History::default();
+    *elapsed = ElapsedTime::default();
+    *source = event.source.clone();
+    selection.0 = None;
+    next_state.set(GameState::Playing);
+}
+
+pub fn mark_solved(source: Res<PuzzleSource>, mut progress: ResMut<Progress>) {
+    match source.as_ref() {
+        PuzzleSource::Daily { date, kind } => {
+            progress.days.insert(format!("{date} {kind}"));
+        }
+        PuzzleSource::Pack { name, index } => {
+            progress
+                .packs
+                .entry(name.clone())
+                .or_default()
+                .insert(*index);
+        }
+        PuzzleSource::Random { .. } | PuzzleSource::Pasted => return,
+    }
+    write_data(PROGRESS_FILE, progress.as_ref());
+}
+
+pub fn show_source(
+    source: Res<PuzzleSource>,
+    progress: Res<Progress>,
+    mut query: Query<&mut Text, With<SourceText>>,
+) {
+    if !(source.is_changed() || progress.is_changed()) {
+        return;
+    }
+    let Ok(mut text) = query.get_single_mut() else {
+        return;
+    };
+
+    text.sections[0].value = match source.as_ref() {
+        PuzzleSource::Daily { date, kind } if progress.days.contains(&format!("{date} {kind}")) => {
+            format!("{} (solved)", *source)
+        }
+        PuzzleSource::Pack { name, .. } => format!(
+            "{} ({} solved)",
+            *source,
+            progress.packs.get(name).map_or(0, BTreeSet::len)
+        ),
+        source => source.to_string(),
+    };
+}
@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::exact_cover::{Matrix, MatrixSize, SolvingState};
+
+// an exact cover problem in terms of the puzzle, items become the columns of the matrix and
+// every option becomes a row covering the items it was added with
+#[derive(Clone, Debug)]
+pub struct ExactCoverProblem<I, O> {
+    primary: Vec<I>,
+    secondary: Vec<I>,
+    columns: HashMap<I, Column>,
+    options: Vec<(O, Vec<Column>)>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+enum Column {
+    // covered exactly once by every solution
+    Primary(usize),
+    // covered at most once by every solution
+    Secondary(usize),
+}
+
+impl<I, O> Default for ExactCoverProblem<I, O> {
+    fn default() -> Self {
+        Self {
+            primary: vec![],
+            secondary: vec![],
+            columns: HashMap::new(),
+            options: vec![],
+        }
+    }
+}
+
+impl<I: Clone + Eq + Hash, O> ExactCoverProblem<I, O> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // an item every solution covers exactly once, adding the same item again does nothing
+    pub fn add_item(&mut self, item: I) {
+        if !self.columns.contains_key(&item) {
+            self.columns
+                .insert(item.clone(), Column::Primary(self.primary.len()));
+            self.primary.push(item);
+        }
+    }
+
+    // an item every solution covers at most once, adding the same item again does nothing
+    pub fn add_secondary_item(&mut self, item: I) {
+        if !self.columns.contains_key(&item) {
+            self.columns
+                .insert(item.clone(), Column::Secondary(self.secondary.len()));
+            self.secondary.push(item);
+        }
+    }
+
+    // an option that covers the given items, which have to be added before
+    pub fn add_option(&mut self, option: O, items: impl IntoIterator<Item = I>) {
+        let columns: Vec<Column> = items
+            .into_iter()
+            .map(|item| {
+                *self
+                    .columns
+                    .get(&item)
+                    .expect("options can only cover items that were added")
+            })
+            .collect();
+        assert!(
+            columns
+                .iter()
+                .any(|column| matches!(column, Column::Primary(_))),
+            "options have to cover at least one primary item"
+        );
+        self.options.push((option, columns));
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = &I> {
+        self.primary.iter().chain(self.secondary.iter())
+    }
+
+    pub fn options(&self) -> impl Iterator<Item = &O> {
+        self.options.iter().map(|(option, _)| option)
+    }
+
+    // calls back with the options of every solution until the callback aborts
+    pub fn solve<'a>(&'a self, mut callback: impl FnMut(Vec<&'a O>) -> SolvingState) {
+        let mut matrix = Matrix::new(MatrixSize {
+            x: self.primary.len(),
+            y: self.options.len(),
+            secondary: self.secondary.len(),
+        });
+        for (_, columns) in self.options.iter() {
+            let row: Vec<usize> = columns
+                .iter()
+                .map(|column| match *column {
+                    Column::Primary(index) => index,
+                    Column::Secondary(index) => self.primary.len() + index,
+                })
+                .collect();
+            matrix.add_row_sparse(&row);
+        }
+
+        matrix.solve(&mut |solution: crate::exact_cover::Solution| {
+            callback(solution.iter().map(|&row| &self.options[row].0).collect())
+        });
+    }
+
+    // the options of the first solution found, if there is one
+    pub fn first_solution(&self) -> Option<Vec<&O>> {
+        let mut found = None;
+        self.solve(|solution| {
+            found = Some(solution);
+            SolvingState::Abort
+        });
+        found
+    }
+}
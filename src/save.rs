@@ -0,0 +1,105 @@
+use std::{fs, path::PathBuf, time::Duration};
+
+use bevy::{app::AppExit, prelude::*};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sudoku::{Choice, Shape};
+
+use crate::board::Board;
+use crate::history::History;
+use crate::packs::PuzzleSource;
+use crate::win::ElapsedTime;
+
+const SAVE_FILE: &str = "save.ron";
+
+// everything needed to pick a game up where the player left it
+#[derive(Serialize, Deserialize)]
+pub struct SavedGame {
+    pub source: PuzzleSource,
+    pub shape: Shape,
+    pub givens: Vec<Choice>,
+    pub entries: Vec<Choice>,
+    pub notes: Vec<u32>,
+    pub elapsed: Duration,
+    pub history: History,
+}
+
+fn data_path(file: &str) -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("sudoku").join(file))
+}
+
+// a missing file is not an error, a broken one is reported and ignored
+pub fn read_data<T: DeserializeOwned>(file: &str) -> Option<T> {
+    let path = data_path(file)?;
+    let contents = fs::read_to_string(&path).ok()?;
+    match ron::from_str(&contents) {
+        Ok(data) => Some(data),
+        Err(error) => {
+            warn!("ignoring {}: {error}", path.display());
+            None
+        }
+    }
+}
+
+pub fn write_data<T: Serialize>(file: &str, data: &T) {
+    let Some(path) = data_path(file) else {
+        warn!("no data directory to write {file} to");
+        return;
+    };
+
+    let result = ron::ser::to_string(data)
+        .map_err(|error| error.to_string())
+        .and_then(|contents| {
+            fs::create_dir_all(path.parent().unwrap())
+                .and_then(|_| fs::write(&path, contents))
+                .map_err(|error| error.to_string())
+        });
+    if let Err(error) = result {
+        warn!("could not write {}: {error}", path.display());
+    }
+}
+
+pub fn load() -> Option<SavedGame> {
+    read_data(SAVE_FILE)
+}
+
+// a finished board is not resumed, the next start generates a fresh one
+pub fn remove() {
+    if let Some(path) = data_path(SAVE_FILE).filter(|path| path.exists()) {
+        if let Err(error) = fs::remove_file(&path) {
+            warn!("could not remove save {}: {error}", path.display());
+        }
+    }
+}
+
+// saved after every move so a crash loses at most the time since the last one
+pub fn save_game(
+    board: Res<Board>,
+    history: Res<History>,
+    elapsed: Res<ElapsedTime>,
+    source: Res<PuzzleSource>,
+) {
+    write_data(
+        SAVE_FILE,
+        &SavedGame {
+            source: source.clone(),
+            shape: board.shape.clone(),
+            givens: board.givens.clone(),
+            entries: board.entries.clone(),
+            notes: board.notes.clone(),
+            elapsed: elapsed.0,
+            history: history.clone(),
+        },
+    );
+}
+
+pub fn save_on_exit(
+    exit_events: EventReader<AppExit>,
+    board: Res<Board>,
+    history: Res<History>,
+    elapsed: Res<ElapsedTime>,
+    source: Res<PuzzleSource>,
+) {
+    if !exit_events.is_empty() {
+        save_game(board, history, elapsed, source);
+    }
+}
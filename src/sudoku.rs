@@ -1,46 +1,266 @@
 use crate::dancing_links::Indexed;
-use crate::exact_cover::{Cell, Matrix, MatrixSize, SolvingState};
+use crate::exact_cover::{Cell, Matrix, MatrixSize, SearchLimit, SolvingState};
+use crate::variant::Variant;
 use itertools::Itertools;
-use rand::seq::{IteratorRandom, SliceRandom};
-use rand::{thread_rng, SeedableRng};
+use rand::seq::SliceRandom;
+use rand::{thread_rng, Rng, SeedableRng};
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+// largest board, numbers are written with a single symbol from 1 to 9 and A to P
+pub const MAX_SIZE: u32 = 25;
+
+// the boxes of a board are box_rows tall and box_columns wide, a board holds as many boxes as
+// a box holds cells, so 2x3 boxes make a 6x6 board
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Shape {
+    pub box_rows: u32,
+    pub box_columns: u32,
+    // jigsaw boards replace the boxes with irregular regions of as many cells,
+    // the region of every cell indexed by row * size + column
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub regions: Option<Vec<u32>>,
+    // extra rules of the board, sorted and without repeats
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub variants: Vec<Variant>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ShapeError(String);
 
-use std::collections::HashSet;
 pub struct Sudoku {
     pub choices: Vec<Choice>,
+    shape: Shape,
+    houses: CellHouses,
     constraints: Vec<Constraint>,
+    // constraints a solution satisfies at most once
+    optional_constraints: Vec<Constraint>,
+}
+
+// the matrix of an empty board, built once and solved again for every set of givens
+#[derive(Clone, Debug)]
+pub struct SudokuMatrix {
+    shape: Shape,
+    choices: Vec<Choice>,
+    // row of every choice in the matrix, indexed by (row * size + column) * size + number
+    rows: Vec<usize>,
+    matrix: Matrix,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
 enum Constraint {
-    RowColumn { row: u32, column: u32 },
-    RowNumber { row: u32, number: u32 },
-    ColumnNumber { column: u32, number: u32 },
-    SquareNumber { square: u32, number: u32 },
+    RowColumn {
+        row: u32,
+        column: u32,
+    },
+    RowNumber {
+        row: u32,
+        number: u32,
+    },
+    ColumnNumber {
+        column: u32,
+        number: u32,
+    },
+    SquareNumber {
+        square: u32,
+        number: u32,
+    },
+    // an extra house of a variant holds the number
+    HouseNumber {
+        variant: Variant,
+        house: u32,
+        number: u32,
+    },
+    // one of two cells a variant keeps apart holds the number, the cells are indexed by
+    // row * size + column with the smaller one first
+    PairNumber {
+        cells: (u32, u32),
+        number: u32,
+    },
+}
+
+impl Shape {
+    // square boxes of n by n cells
+    pub fn square(n: u32) -> Self {
+        Self {
+            box_rows: n,
+            box_columns: n,
+            regions: None,
+            variants: vec![],
+        }
+    }
+
+    // the usual boxes for a board size, as square as possible and wider than tall,
+    // None when the size only splits into boxes one cell tall
+    pub fn for_size(size: u32) -> Option<Self> {
+        let box_rows = (1..=size)
+            .take_while(|rows| rows * rows <= size)
+            .filter(|rows| size % rows == 0)
+            .last()?;
+        (box_rows > 1 || size == 1).then_some(Self {
+            box_rows,
+            box_columns: size / box_rows,
+            regions: None,
+            variants: vec![],
+        })
+    }
+
+    // the same board size with the boxes replaced by regions, see regions
+    pub fn with_regions(&self, regions: Vec<u32>) -> Self {
+        Self {
+            regions: Some(regions),
+            ..self.clone()
+        }
+    }
+
+    // the same board with these extra rules
+    pub fn with_variants(&self, variants: impl IntoIterator<Item = Variant>) -> Self {
+        Self {
+            variants: variants.into_iter().sorted().dedup().collect(),
+            ..self.clone()
+        }
+    }
+
+    // cells in a row, a column or a box, and also the count of numbers
+    pub fn size(&self) -> u32 {
+        self.box_rows * self.box_columns
+    }
+
+    pub fn cells(&self) -> u32 {
+        self.size().pow(2)
+    }
+
+    // the box or region of a cell, boxes are counted left to right, top to bottom
+    pub fn square_of(&self, row: u32, column: u32) -> u32 {
+        match &self.regions {
+            Some(regions) => regions[(row * self.size() + column) as usize],
+            // a row of boxes holds box_rows boxes
+            None => (row / self.box_rows) * self.box_rows + column / self.box_columns,
+        }
+    }
+
+    // cells of a box or region as (row, column)
+    pub fn square_cells(&self, square: u32) -> Vec<(u32, u32)> {
+        (0..self.size())
+            .cartesian_product(0..self.size())
+            .filter(|&(row, column)| self.square_of(row, column) == square)
+            .collect()
+    }
+}
+
+impl Default for Shape {
+    fn default() -> Self {
+        Self::square(3)
+    }
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.box_rows, self.box_columns)
+    }
+}
+
+impl fmt::Display for ShapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ShapeError {}
+
+// either N for square boxes or RxC for boxes R cells tall and C cells wide
+impl FromStr for Shape {
+    type Err = ShapeError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let dimension = |text: &str| {
+            text.trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|&dimension| dimension > 0)
+                .ok_or_else(|| ShapeError(format!("`{text}` is not a box dimension")))
+        };
+        let shape = match text.split_once(['x', 'X']) {
+            Some((rows, columns)) => Shape {
+                box_rows: dimension(rows)?,
+                box_columns: dimension(columns)?,
+                regions: None,
+                variants: vec![],
+            },
+            None => Shape::square(dimension(text)?),
+        };
+
+        if shape.size() > MAX_SIZE {
+            return Err(ShapeError(format!(
+                "a {shape} box holds more than {MAX_SIZE} cells"
+            )));
+        }
+        Ok(shape)
+    }
 }
 
 impl Constraint {
-    pub fn all(n: u32) -> impl Iterator<Item = Constraint> {
-        let row_column_iter = (0..n.pow(2))
-            .cartesian_product(0..n.pow(2))
+    // constraints every solution satisfies exactly once
+    fn all(shape: &Shape) -> Vec<Constraint> {
+        let size = shape.size();
+        let row_column_iter = (0..size)
+            .cartesian_product(0..size)
             .map(|(row, column)| Constraint::RowColumn { row, column });
-        let row_number_iter = (0..n.pow(2))
-            .cartesian_product(0..n.pow(2))
+        let row_number_iter = (0..size)
+            .cartesian_product(0..size)
             .map(|(row, number)| Constraint::RowNumber { row, number });
-        let column_number_iter = (0..n.pow(2))
-            .cartesian_product(0..n.pow(2))
+        let column_number_iter = (0..size)
+            .cartesian_product(0..size)
             .map(|(column, number)| Constraint::ColumnNumber { column, number });
-        let square_number_iter = (0..n.pow(2))
-            .cartesian_product(0..n.pow(2))
+        let square_number_iter = (0..size)
+            .cartesian_product(0..size)
             .map(|(square, number)| Constraint::SquareNumber { square, number });
 
+        let house_number_iter = shape.variants.iter().flat_map(|&variant| {
+            (0..variant.houses(shape).len() as u32)
+                .cartesian_product(0..size)
+                .map(move |(house, number)| Constraint::HouseNumber {
+                    variant,
+                    house,
+                    number,
+                })
+        });
+
         row_column_iter
             .chain(row_number_iter)
             .chain(column_number_iter)
             .chain(square_number_iter)
+            .chain(house_number_iter)
+            .collect()
+    }
+
+    // constraints a solution satisfies at most once
+    fn optional(shape: &Shape) -> Vec<Constraint> {
+        let size = shape.size();
+        let mut constraints = vec![];
+        for variant in shape.variants.iter() {
+            for (row, column) in (0..size).cartesian_product(0..size) {
+                let cell = row * size + column;
+                for (other_row, other_column) in variant.forbidden_cells(shape, row, column) {
+                    let other = other_row * size + other_column;
+                    if cell < other {
+                        constraints.extend((0..size).map(|number| Constraint::PairNumber {
+                            cells: (cell, other),
+                            number,
+                        }));
+                    }
+                }
+            }
+        }
+        constraints
     }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Choice {
     pub row: u32,
     pub column: u32,
@@ -57,36 +277,48 @@ impl std::ops::Index<Cell> for Vec<Choice> {
 }
 
 impl Choice {
-    pub fn all(n: u32) -> impl Iterator<Item = Choice> {
-        // let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
-        let mut rng = thread_rng();
-        let mut row_range = (0..n.pow(2)).collect::<Vec<u32>>();
-        row_range.shuffle(&mut rng);
-        let mut column_range = (0..n.pow(2)).collect::<Vec<u32>>();
-        column_range.shuffle(&mut rng);
-        let mut number_range = (0..n.pow(2)).collect::<Vec<u32>>();
-        number_range.shuffle(&mut rng);
-
-        row_range
+    pub fn all(shape: &Shape, rng: &mut impl Rng) -> impl Iterator<Item = Choice> {
+        let size = shape.size();
+        let mut row_range = (0..size).collect::<Vec<u32>>();
+        row_range.shuffle(rng);
+        let mut column_range = (0..size).collect::<Vec<u32>>();
+        column_range.shuffle(rng);
+
+        // every cell tries the numbers in an order of its own
+        let mut choices = Vec::with_capacity(size.pow(3) as usize);
+        for (&row, &column) in row_range.iter().cartesian_product(column_range.iter()) {
+            let mut number_range = (0..size).collect::<Vec<u32>>();
+            number_range.shuffle(rng);
+            choices.extend(
+                number_range
+                    .into_iter()
+                    .map(|number| Choice::new(shape, row, column, number)),
+            );
+        }
+        choices.into_iter()
+    }
+
+    pub fn new(shape: &Shape, row: u32, column: u32, number: u32) -> Self {
+        Choice {
+            row,
+            column,
+            square: shape.square_of(row, column),
+            number,
+        }
+    }
+
+    // two choices conflict when they would both satisfy the same constraint
+    pub fn conflicts_with(&self, shape: &Shape, houses: &CellHouses, other: &Choice) -> bool {
+        let constraints = other.satisfied_constraints(shape, houses);
+        self.satisfied_constraints(shape, houses)
             .iter()
-            .cartesian_product(column_range)
-            .cartesian_product(number_range)
-            .map(move |((row, column), number)| {
-                let index = (row * n.pow(2)) + column;
-                let square = ((index % n.pow(2)) / n) + (n * (index / (n.pow(3))));
-                Choice {
-                    row: *row,
-                    column,
-                    square,
-                    number,
-                }
-            })
-            .collect::<Vec<Choice>>()
-            .into_iter()
+            .any(|constraint| constraints.contains(constraint))
     }
 
-    fn satisfied_constraints(choice: &Choice) -> impl Iterator<Item = Constraint> {
-        [
+    fn satisfied_constraints(&self, shape: &Shape, houses: &CellHouses) -> Vec<Constraint> {
+        let choice = self;
+        let size = shape.size();
+        let mut constraints = vec![
             Constraint::RowColumn {
                 row: choice.row,
                 column: choice.column,
@@ -103,56 +335,359 @@ impl Choice {
                 square: choice.square,
                 number: choice.number,
             },
-        ]
-        .into_iter()
+        ];
+
+        let cell = choice.row * size + choice.column;
+        for &(variant, house) in houses.of(choice.row, choice.column) {
+            constraints.push(Constraint::HouseNumber {
+                variant,
+                house,
+                number: choice.number,
+            });
+        }
+        for &variant in shape.variants.iter() {
+            for (row, column) in variant.forbidden_cells(shape, choice.row, choice.column) {
+                let other = row * size + column;
+                constraints.push(Constraint::PairNumber {
+                    cells: (cell.min(other), cell.max(other)),
+                    number: choice.number,
+                });
+            }
+        }
+        constraints
     }
 }
 pub type Solution = Vec<Choice>;
 
-impl<'a> Sudoku {
-    pub fn new(n: u32, filled_values: impl IntoIterator<Item = Choice>) -> Self {
+// the houses of variants every cell lies in, built once for a shape so choices look up their
+// houses rather than going through all of them
+#[derive(Clone, Debug)]
+pub struct CellHouses {
+    size: u32,
+    // (variant, house) pairs indexed by row * size + column
+    cells: Vec<Vec<(Variant, u32)>>,
+}
+
+impl CellHouses {
+    pub fn new(shape: &Shape) -> Self {
+        let size = shape.size();
+        let mut cells = vec![vec![]; shape.cells() as usize];
+        for &variant in shape.variants.iter() {
+            for (house, house_cells) in variant.houses(shape).into_iter().enumerate() {
+                for (row, column) in house_cells {
+                    cells[(row * size + column) as usize].push((variant, house as u32));
+                }
+            }
+        }
+        Self { size, cells }
+    }
+
+    // cells off the board lie in no house
+    pub fn of(&self, row: u32, column: u32) -> &[(Variant, u32)] {
+        if row < self.size && column < self.size {
+            &self.cells[(row * self.size + column) as usize]
+        } else {
+            &[]
+        }
+    }
+}
+
+// seeds are expanded with ChaCha8 so the same seed yields the same board on every platform
+pub fn rng_from_seed(seed: u64) -> rand_chacha::ChaCha8Rng {
+    rand_chacha::ChaCha8Rng::seed_from_u64(seed)
+}
+
+// values that satisfy a constraint another value satisfies too, they break a rule of the board
+pub fn conflicting(shape: &Shape, values: &[Choice]) -> Vec<Choice> {
+    let houses = CellHouses::new(shape);
+    let satisfied = values
+        .iter()
+        .flat_map(|value| value.satisfied_constraints(shape, &houses))
+        .counts();
+
+    values
+        .iter()
+        .filter(|value| {
+            value
+                .satisfied_constraints(shape, &houses)
+                .iter()
+                .any(|c| satisfied[c] > 1)
+        })
+        .copied()
+        .collect()
+}
+
+// a board is complete once every constraint is satisfied by exactly one value,
+// and no optional one by more
+pub fn is_complete(shape: &Shape, values: &[Choice]) -> bool {
+    let houses = CellHouses::new(shape);
+    let satisfied = values
+        .iter()
+        .flat_map(|value| value.satisfied_constraints(shape, &houses))
+        .counts();
+
+    Constraint::all(shape)
+        .iter()
+        .all(|c| satisfied.get(c) == Some(&1))
+        && satisfied.values().all(|&count| count == 1)
+}
+
+impl Sudoku {
+    pub fn new(shape: &Shape, filled_values: impl IntoIterator<Item = Choice>) -> Self {
+        Self::with_rng(shape, filled_values, &mut thread_rng())
+    }
+
+    pub fn with_seed(
+        shape: &Shape,
+        filled_values: impl IntoIterator<Item = Choice>,
+        seed: u64,
+    ) -> Self {
+        Self::with_rng(shape, filled_values, &mut rng_from_seed(seed))
+    }
+
+    // the rng decides the order in which the solver tries choices, and so which solution is found first
+    pub fn with_rng(
+        shape: &Shape,
+        filled_values: impl IntoIterator<Item = Choice>,
+        rng: &mut impl Rng,
+    ) -> Self {
         let filled_values: Vec<Choice> = filled_values.into_iter().collect();
+        let houses = CellHouses::new(shape);
 
         let satisfied: HashSet<_> = filled_values
             .iter()
-            .flat_map(Choice::satisfied_constraints)
+            .flat_map(|value| value.satisfied_constraints(shape, &houses))
             .collect();
 
-        let filled_coordinates: HashSet<_> = filled_values
-            .iter()
-            .map(|choice| (choice.row, choice.column))
+        // a choice that shares a constraint with a filled value can never be part of a solution,
+        // this also excludes every choice for the filled cells themselves
+        let choices: Vec<Choice> = Choice::all(shape, rng)
+            .filter(|choice| {
+                !choice
+                    .satisfied_constraints(shape, &houses)
+                    .iter()
+                    .any(|c| satisfied.contains(c))
+            })
             .collect();
 
-        let choices: Vec<Choice> = Choice::all(n)
-            .filter(|c| !filled_coordinates.contains(&(c.row, c.column)))
+        let constraints: Vec<Constraint> = Constraint::all(shape)
+            .into_iter()
+            .filter(|c| !satisfied.contains(c))
             .collect();
-
-        let constraints: Vec<Constraint> = Constraint::all(n)
+        let optional_constraints: Vec<Constraint> = Constraint::optional(shape)
+            .into_iter()
             .filter(|c| !satisfied.contains(c))
             .collect();
 
         Self {
             choices,
+            shape: shape.clone(),
+            houses,
             constraints,
+            optional_constraints,
         }
     }
 
-    pub fn solve(&self, mut callback: impl FnMut(Solution) -> SolvingState) {
-        let mut matrix = Matrix::new(MatrixSize {
-            x: self.constraints.len(),
-            y: self.choices.len(),
-        });
-        for choice in self.choices.iter() {
-            let row = self
-                .constraints
-                .iter()
-                .map(move |constraint| Choice::satisfied_constraints(choice).contains(constraint))
-                .collect::<Vec<bool>>();
-            matrix.add_row(&row);
-        }
+    pub fn solve(&self, callback: impl FnMut(Solution) -> SolvingState) {
+        self.solve_within(SearchLimit::default(), callback);
+    }
 
+    // like solve, but the search gives up once the limit runs out, returns false when it did
+    pub fn solve_within(
+        &self,
+        limit: SearchLimit,
+        mut callback: impl FnMut(Solution) -> SolvingState,
+    ) -> bool {
+        let mut matrix = build_matrix(
+            &self.shape,
+            &self.houses,
+            &self.choices,
+            &self.constraints,
+            &self.optional_constraints,
+        );
+        matrix.set_limit(limit);
         matrix.solve(&mut |solution: crate::exact_cover::Solution| {
             return callback(solution.iter().map(|row| self.choices[*row]).collect());
         });
+        !matrix.gave_up()
+    }
+
+    // the number of solutions, counting stops once the limit is reached
+    pub fn count_solutions(&self, limit: Option<usize>) -> usize {
+        build_matrix(
+            &self.shape,
+            &self.houses,
+            &self.choices,
+            &self.constraints,
+            &self.optional_constraints,
+        )
+        .count_solutions(limit)
+    }
+
+    pub fn is_unique(&self) -> bool {
+        self.count_solutions(Some(2)) == 1
+    }
+}
+
+impl SudokuMatrix {
+    pub fn new(shape: &Shape) -> Self {
+        Self::with_rng(shape, &mut thread_rng())
+    }
+
+    // the rng decides the order in which the solver tries choices, and so which solution is found first
+    pub fn with_rng(shape: &Shape, rng: &mut impl Rng) -> Self {
+        let choices: Vec<Choice> = Choice::all(shape, rng).collect();
+        let matrix = build_matrix(
+            shape,
+            &CellHouses::new(shape),
+            &choices,
+            &Constraint::all(shape),
+            &Constraint::optional(shape),
+        );
+
+        let size = shape.size();
+        let mut rows = vec![0; choices.len()];
+        for (index, choice) in choices.iter().enumerate() {
+            rows[((choice.row * size + choice.column) * size + choice.number) as usize] = index;
+        }
+
+        Self {
+            shape: shape.clone(),
+            choices,
+            rows,
+            matrix,
+        }
+    }
+
+    pub fn shape(&self) -> &Shape {
+        &self.shape
+    }
+
+    // calls back with the values that complete the givens, like Sudoku::solve, givens that
+    // break a rule have no solution. the matrix is left as it was for the next givens
+    pub fn solve(&mut self, givens: &[Choice], mut callback: impl FnMut(Solution) -> SolvingState) {
+        if self.select_givens(givens) {
+            let choices = &self.choices;
+            self.matrix
+                .solve(&mut |solution: crate::exact_cover::Solution| {
+                    callback(solution.iter().map(|row| choices[*row]).collect())
+                });
+        }
+        self.matrix.deselect_rows();
+    }
+
+    // the number of ways to complete the givens, counting stops once the limit is reached
+    pub fn count_solutions(&mut self, givens: &[Choice], limit: Option<usize>) -> usize {
+        let count = if self.select_givens(givens) {
+            self.matrix.count_solutions(limit)
+        } else {
+            0
+        };
+        self.matrix.deselect_rows();
+        count
+    }
+
+    pub fn is_unique(&mut self, givens: &[Choice]) -> bool {
+        self.count_solutions(givens, Some(2)) == 1
+    }
+
+    // false when the givens break a rule or do not fit on the board
+    fn select_givens(&mut self, givens: &[Choice]) -> bool {
+        let shape = &self.shape;
+        let size = shape.size();
+        givens.iter().all(|given| {
+            if given.row >= size || given.column >= size || given.number >= size {
+                return false;
+            }
+            if given.square != shape.square_of(given.row, given.column) {
+                return false;
+            }
+            let index = (given.row * size + given.column) * size + given.number;
+            self.matrix.select_row(self.rows[index as usize])
+        })
+    }
+}
+
+// a row for every choice, covering the columns of the constraints it satisfies
+fn build_matrix(
+    shape: &Shape,
+    houses: &CellHouses,
+    choices: &[Choice],
+    constraints: &[Constraint],
+    optional_constraints: &[Constraint],
+) -> Matrix {
+    let columns: HashMap<&Constraint, usize> = constraints
+        .iter()
+        .chain(optional_constraints.iter())
+        .enumerate()
+        .map(|(index, constraint)| (constraint, index))
+        .collect();
+
+    let mut matrix = Matrix::new(MatrixSize {
+        x: constraints.len(),
+        y: choices.len(),
+        secondary: optional_constraints.len(),
+    });
+    for choice in choices {
+        // choices that share a constraint with a filled value were left out, so every
+        // constraint of the rest has a column
+        let row = choice
+            .satisfied_constraints(shape, houses)
+            .iter()
+            .map(|constraint| columns[constraint])
+            .collect::<Vec<usize>>();
+        matrix.add_row_sparse(&row);
+    }
+    matrix
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn givens_are_completed() {
+        let shape = Shape::square(2);
+        let mut matrix = SudokuMatrix::with_rng(&shape, &mut rng_from_seed(0));
+        assert_eq!(matrix.count_solutions(&[], None), 288);
+
+        let givens = [
+            Choice::new(&shape, 0, 0, 0),
+            Choice::new(&shape, 0, 1, 1),
+            Choice::new(&shape, 1, 0, 2),
+        ];
+        let solutions = matrix.count_solutions(&givens, None);
+        assert!(solutions > 0 && solutions < 288);
+        // the matrix is left as it was for the next givens
+        assert_eq!(matrix.count_solutions(&[], None), 288);
+    }
+
+    #[test]
+    fn conflicting_givens_have_no_solution() {
+        let shape = Shape::square(2);
+        let mut matrix = SudokuMatrix::with_rng(&shape, &mut rng_from_seed(0));
+        let givens = [Choice::new(&shape, 0, 0, 0), Choice::new(&shape, 0, 3, 0)];
+        assert_eq!(matrix.count_solutions(&givens, None), 0);
+        assert!(!matrix.is_unique(&givens));
+        assert_eq!(matrix.count_solutions(&[], None), 288);
+    }
+
+    #[test]
+    fn givens_off_the_board_have_no_solution() {
+        let shape = Shape::square(2);
+        let mut matrix = SudokuMatrix::with_rng(&shape, &mut rng_from_seed(0));
+        let off_board = [
+            Choice::new(&shape, 4, 0, 0),
+            Choice::new(&shape, 0, 4, 0),
+            Choice::new(&shape, 0, 0, 4),
+            Choice {
+                square: 3,
+                ..Choice::new(&shape, 0, 0, 0)
+            },
+        ];
+        for given in off_board {
+            assert_eq!(matrix.count_solutions(&[given], None), 0, "{given:?}");
+        }
+        assert_eq!(matrix.count_solutions(&[], None), 288);
     }
 }
@@ -0,0 +1,94 @@
+use itertools::Itertools;
+
+use crate::sudoku::Shape;
+
+// extra rules on top of the rows, columns and boxes of a board
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Variant {
+    // both main diagonals hold every number once
+    Diagonal,
+    // extra boxes set one cell in from the regular ones hold every number once
+    Windoku,
+    // cells a knight's move apart never hold the same number
+    AntiKnight,
+    // cells a king's move apart never hold the same number
+    AntiKing,
+}
+
+impl Variant {
+    pub const ALL: [Variant; 4] = [
+        Variant::Diagonal,
+        Variant::Windoku,
+        Variant::AntiKnight,
+        Variant::AntiKing,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Variant::Diagonal => "diagonal",
+            Variant::Windoku => "windoku",
+            Variant::AntiKnight => "anti-knight",
+            Variant::AntiKing => "anti-king",
+        }
+    }
+
+    // groups of cells as (row, column) that hold every number exactly once
+    pub fn houses(&self, shape: &Shape) -> Vec<Vec<(u32, u32)>> {
+        let size = shape.size();
+        match self {
+            Variant::Diagonal => vec![
+                (0..size).map(|index| (index, index)).collect(),
+                (0..size).map(|index| (index, size - 1 - index)).collect(),
+            ],
+            Variant::Windoku => {
+                // windows are one cell apart and keep clear of the edges of the board
+                let starts = |length: u32| {
+                    (1..size)
+                        .step_by(length as usize + 1)
+                        .filter(move |start| start + length < size)
+                };
+                starts(shape.box_rows)
+                    .cartesian_product(starts(shape.box_columns).collect::<Vec<u32>>())
+                    .map(|(top, left)| {
+                        (top..top + shape.box_rows)
+                            .cartesian_product(left..left + shape.box_columns)
+                            .collect()
+                    })
+                    .collect()
+            }
+            Variant::AntiKnight | Variant::AntiKing => vec![],
+        }
+    }
+
+    // cells as (row, column) that may not hold the same number as the cell at row and column
+    pub fn forbidden_cells(
+        &self,
+        shape: &Shape,
+        row: u32,
+        column: u32,
+    ) -> impl Iterator<Item = (u32, u32)> {
+        let offsets: &[(i64, i64)] = match self {
+            Variant::AntiKnight => &[
+                (-2, -1),
+                (-2, 1),
+                (-1, -2),
+                (-1, 2),
+                (1, -2),
+                (1, 2),
+                (2, -1),
+                (2, 1),
+            ],
+            // the other neighbours already share a row or a column with the cell
+            Variant::AntiKing => &[(-1, -1), (-1, 1), (1, -1), (1, 1)],
+            Variant::Diagonal | Variant::Windoku => &[],
+        };
+
+        let size = i64::from(shape.size());
+        offsets.iter().filter_map(move |&(rows, columns)| {
+            let (row, column) = (i64::from(row) + rows, i64::from(column) + columns);
+            ((0..size).contains(&row) && (0..size).contains(&column))
+                .then_some((row as u32, column as u32))
+        })
+    }
+}
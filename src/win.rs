@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::board::Board;
+
+pub const WIN_COLOR: Color = Color::rgb(0.6, 1., 0.6);
+
+#[derive(Component)]
+pub struct WinText;
+
+// time spent playing the current board, it only runs while playing
+#[derive(Resource, Default)]
+pub struct ElapsedTime(pub Duration);
+
+// the board can only be edited while playing, it is locked once solved
+#[derive(States, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum GameState {
+    #[default]
+    Playing,
+    Won,
+}
+
+pub fn check_win(board: Res<Board>, mut next_state: ResMut<NextState<GameState>>) {
+    let choices: Vec<_> = board.choices().collect();
+    if sudoku::is_complete(&board.shape, &choices) {
+        next_state.set(GameState::Won);
+    }
+}
+
+pub fn tick_elapsed_time(time: Res<Time>, mut elapsed: ResMut<ElapsedTime>) {
+    elapsed.0 += time.delta();
+}
+
+pub fn show_win(elapsed: Res<ElapsedTime>, mut query: Query<&mut Text, With<WinText>>) {
+    let seconds = elapsed.0.as_secs();
+    if let Ok(mut text) = query.get_single_mut() {
+        text.sections[0].value = format!("Solved in {}:{:02}!", seconds / 60, seconds % 60);
+    }
+}
+
+pub fn hide_win(mut query: Query<&mut Text, With<WinText>>) {
+    if let Ok(mut text) = query.get_single_mut() {
+        text.sections[0].value.clear();
+    }
+}